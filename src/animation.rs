@@ -0,0 +1,196 @@
+//! Plays a puzzle's simulation in the terminal, redrawing each step in place with ANSI escape codes.
+
+use std::fmt::Write as _;
+use std::io::{IsTerminal, Stdout, Write};
+use std::time::{Duration, Instant};
+
+use color_eyre::eyre::eyre;
+use color_eyre::Report;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::terminal;
+
+use crate::grid::Grid;
+use crate::picture::Color;
+
+/// A puzzle state that can be advanced and drawn one step at a time
+pub trait Simulation {
+    /// Advances the state by one step
+    fn step(&mut self);
+
+    /// Draws the current state, which may include ANSI colour codes
+    fn frame(&self) -> String;
+
+    /// Whether there's nothing left worth stepping through
+    fn is_finished(&self) -> bool { false }
+
+    /// The settings that can be changed with `set`, and what each is now
+    fn params(&self) -> Vec<(&'static str, String)> { vec![] }
+
+    /// Changes one of the settings listed by `params`
+    fn set(&mut self, param: &str, _value: &str) -> Result<(), Report> {
+        Err(eyre!("There's no setting called {}", param))
+    }
+}
+
+const MIN_FPS: f64 = 0.5;
+const MAX_FPS: f64 = 120.0;
+
+/// Switches the terminal to raw mode on the alternate screen, and puts it back when dropped
+struct Screen;
+
+impl Screen {
+    fn enter(out: &mut Stdout) -> Result<Screen, Report> {
+        terminal::enable_raw_mode()?;
+        // Alternate screen, hidden cursor, no line wrapping
+        write!(out, "\x1b[?1049h\x1b[?25l\x1b[?7l")?;
+        out.flush()?;
+        Ok(Screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let mut out = std::io::stdout();
+        let _ = write!(out, "\x1b[?7h\x1b[?25h\x1b[?1049l");
+        let _ = out.flush();
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// What a key press asks the player to do
+enum Control {
+    TogglePause,
+    Step,
+    Faster,
+    Slower,
+    Quit,
+}
+
+fn control(key: KeyEvent) -> Option<Control> {
+    match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Some(Control::Quit),
+        KeyCode::Char(' ') => Some(Control::TogglePause),
+        KeyCode::Char('.') | KeyCode::Char('n') | KeyCode::Right => Some(Control::Step),
+        KeyCode::Char('+') | KeyCode::Char('=') | KeyCode::Up => Some(Control::Faster),
+        KeyCode::Char('-') | KeyCode::Down => Some(Control::Slower),
+        KeyCode::Char('q') | KeyCode::Esc => Some(Control::Quit),
+        _ => None,
+    }
+}
+
+/// Where the player is up to, shown on the status line
+struct Progress<'a> {
+    title: &'a str,
+    steps: usize,
+    fps: f64,
+    paused: bool,
+    finished: bool,
+}
+
+fn draw(out: &mut Stdout, progress: &Progress, frame: &str) -> Result<(), Report> {
+    let (_, rows) = terminal::size()?;
+    let state = if progress.finished { "  finished" } else if progress.paused { "  paused" } else { "" };
+
+    let mut screen = String::from("\x1b[H");
+    write!(screen, "\x1b[1m{}\x1b[0m  step {}  {:.1} fps{}   [space] pause  [.] step  [+/-] speed  [q] quit\x1b[K\r\n",
+           progress.title, progress.steps, progress.fps, state)?;
+    // Leave the bottom row free so the screen never scrolls
+    for line in frame.lines().take((rows as usize).saturating_sub(2)) {
+        write!(screen, "{}\x1b[0m\x1b[K\r\n", line)?;
+    }
+    screen.push_str("\x1b[J");
+
+    out.write_all(screen.as_bytes())?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Runs `simulation` in the terminal at `fps` steps a second until the viewer quits, returning how many
+/// steps were taken. Space pauses, `.` steps once, `+` and `-` change the speed and `q` quits.
+pub fn play(title: &str, simulation: &mut dyn Simulation, fps: f64) -> Result<usize, Report> {
+    let mut out = std::io::stdout();
+    if !out.is_terminal() {
+        return Err(eyre!("Animation needs stdout to be a terminal"))
+    }
+    let _screen = Screen::enter(&mut out)?;
+
+    let mut progress = Progress { title, steps: 0, fps: fps.clamp(MIN_FPS, MAX_FPS), paused: false, finished: false };
+    loop {
+        progress.finished = simulation.is_finished();
+        draw(&mut out, &progress, &simulation.frame())?;
+
+        // Wait for the next frame, handling key presses as they come in
+        let deadline = Instant::now() + Duration::from_secs_f64(1.0 / progress.fps);
+        let advance = loop {
+            let waiting = progress.paused || progress.finished;
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if !waiting && remaining.is_zero() { break true }
+            if !waiting && !event::poll(remaining)? { continue }
+
+            match event::read()? {
+                Event::Key(key) => match control(key) {
+                    Some(Control::Quit) => return Ok(progress.steps),
+                    Some(Control::TogglePause) => {
+                        progress.paused = !progress.paused;
+                        break false
+                    }
+                    Some(Control::Step) => {
+                        progress.paused = true;
+                        break true
+                    }
+                    Some(Control::Faster) => {
+                        progress.fps = (progress.fps * 1.5).min(MAX_FPS);
+                        break false
+                    }
+                    Some(Control::Slower) => {
+                        progress.fps = (progress.fps / 1.5).max(MIN_FPS);
+                        break false
+                    }
+                    None => {}
+                },
+                Event::Resize(..) => break false,
+                _ => {}
+            }
+        };
+
+        if advance && !progress.finished {
+            simulation.step();
+            progress.steps += 1;
+        }
+    }
+}
+
+/// Sets the foreground colour, or the background if `background`
+fn ansi_color(s: &mut String, color: Color, background: bool) {
+    let _ = write!(s, "\x1b[{};2;{};{};{}m", if background { 48 } else { 38 }, color.r, color.g, color.b);
+}
+
+/// Draws a grid two rows to a line with half-block characters, so the cells come out roughly square
+pub fn half_blocks<T>(grid: &Grid<T>, mut color: impl FnMut(&T) -> Color) -> String {
+    let mut s = String::new();
+    let rows: Vec<_> = grid.rows().collect();
+    for pair in rows.chunks(2) {
+        for col in 0..grid.width() {
+            ansi_color(&mut s, color(&pair[0][col]), false);
+            match pair.get(1) {
+                Some(bottom) => ansi_color(&mut s, color(&bottom[col]), true),
+                None => s.push_str("\x1b[49m"),
+            }
+            s.push('▀');
+        }
+        s.push_str("\x1b[0m\n");
+    }
+    s
+}
+
+/// Draws labelled horizontal bars, scaled so that the longest is `width` characters
+pub fn bar_chart(bars: &[(String, u64)], width: usize) -> String {
+    let longest = bars.iter().map(|(_, value)| *value).max().unwrap_or(0).max(1);
+    let label_width = bars.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+    let mut s = String::new();
+    for (label, value) in bars {
+        let length = (*value as u128 * width as u128 / longest as u128) as usize;
+        let _ = writeln!(s, "{:>label_width$} {} {}", label, "█".repeat(length), value, label_width = label_width);
+    }
+    s
+}
@@ -0,0 +1,75 @@
+use std::fmt::{Display, Formatter};
+
+use serde::{Serialize, Serializer};
+
+/// The result of solving one part of a puzzle
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Answer {
+    Integer(i64),
+    /// Values that don't fit in an i64
+    Big(u128),
+    Text(String),
+    /// Multi-line output, such as letters drawn with `#` and `.`
+    Grid(Vec<String>),
+}
+
+impl Answer {
+    /// Builds a grid answer from newline-separated rows, ignoring empty rows
+    pub fn grid(s: &str) -> Answer {
+        Answer::Grid(s.split('\n').filter(|row| !row.is_empty()).map(|row| row.to_owned()).collect())
+    }
+
+    pub fn is_multi_line(&self) -> bool {
+        matches!(self, Answer::Grid(rows) if rows.len() > 1)
+    }
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Answer::Integer(n) => write!(f, "{}", n),
+            Answer::Big(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+            Answer::Grid(rows) => write!(f, "{}", rows.join("\n")),
+        }
+    }
+}
+
+impl Serialize for Answer {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Answer::Integer(n) => serializer.serialize_i64(*n),
+            // Plenty of JSON readers lose precision above 2^53, so big values are written as strings
+            other => serializer.serialize_str(&other.to_string()),
+        }
+    }
+}
+
+macro_rules! answer_from_integer {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(n: $t) -> Answer {
+                    match i64::try_from(n) {
+                        Ok(n) => Answer::Integer(n),
+                        Err(_) => Answer::Big(n as u128),
+                    }
+                }
+            }
+        )*
+    }
+}
+
+answer_from_integer!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64);
+
+impl From<String> for Answer {
+    fn from(s: String) -> Answer {
+        Answer::Text(s)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(s: &str) -> Answer {
+        Answer::Text(s.to_owned())
+    }
+}
@@ -0,0 +1,127 @@
+use std::mem::swap;
+
+use crate::grid::{Grid, ORTHOGONAL, SURROUNDING};
+
+/// Every cell in the 3x3 block centred on a cell, including the cell itself, in reading order
+pub const BLOCK: [(isize, isize); 9] = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 0), (0, 1), (1, -1), (1, 0), (1, 1)];
+
+/// Which cells' values an update rule gets to see
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Neighborhood {
+    /// The four orthogonally adjacent cells
+    VonNeumann,
+    /// All eight surrounding cells
+    Moore,
+    /// The 3x3 block including the cell itself
+    Block,
+    /// Any other set of (row, column) offsets, passed to the rule in this order
+    Custom(Vec<(isize, isize)>),
+}
+
+impl Neighborhood {
+    pub fn offsets(&self) -> &[(isize, isize)] {
+        match self {
+            Neighborhood::VonNeumann => &ORTHOGONAL,
+            Neighborhood::Moore => &SURROUNDING,
+            Neighborhood::Block => &BLOCK,
+            Neighborhood::Custom(offsets) => offsets,
+        }
+    }
+
+    /// How far the neighborhood reaches from the cell in any direction
+    fn reach(&self) -> usize {
+        self.offsets().iter().map(|&(dr, dc)| dr.unsigned_abs().max(dc.unsigned_abs())).max().unwrap_or(0)
+    }
+}
+
+/// What lies beyond the edges of the grid
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Boundary<T> {
+    /// Every cell off the grid has this value, forever
+    Fixed(T),
+    /// The grid wraps around, so the cell off the right edge is the leftmost one
+    Wrapping,
+    /// The grid is a window on an infinite plane filled with this value. The window grows as each step
+    /// reaches further out, and the background itself changes as the rule is applied to it.
+    Infinite(T),
+}
+
+/// Runs an update rule over every cell of a grid at once
+#[derive(Debug, Clone)]
+pub struct Automaton<T> {
+    cells: Grid<T>,
+    /// The next generation is written here and then swapped in, so it's only allocated when the grid grows
+    buffer: Grid<T>,
+    neighborhood: Neighborhood,
+    boundary: Boundary<T>,
+    generation: usize,
+}
+
+fn lookup<'a, T>(cells: &'a Grid<T>, boundary: &'a Boundary<T>, row: isize, col: isize) -> &'a T {
+    match boundary {
+        Boundary::Wrapping => &cells[(row.rem_euclid(cells.height() as isize) as usize, col.rem_euclid(cells.width() as isize) as usize)],
+        Boundary::Fixed(outside) | Boundary::Infinite(outside) => cells.get_signed(row, col).unwrap_or(outside),
+    }
+}
+
+impl<T: Clone + PartialEq> Automaton<T> {
+    pub fn new(cells: Grid<T>, neighborhood: Neighborhood, boundary: Boundary<T>) -> Automaton<T> {
+        let buffer = cells.clone();
+        Automaton { cells, buffer, neighborhood, boundary, generation: 0 }
+    }
+
+    pub fn cells(&self) -> &Grid<T> { &self.cells }
+
+    /// How many steps have been run
+    pub fn generation(&self) -> usize { self.generation }
+
+    /// The value of every cell outside the grid, if the boundary gives them one
+    pub fn background(&self) -> Option<&T> {
+        match &self.boundary {
+            Boundary::Fixed(outside) | Boundary::Infinite(outside) => Some(outside),
+            Boundary::Wrapping => None,
+        }
+    }
+
+    /// Replaces every cell with `rule(cell, neighbors)` at once, returning how many of them changed
+    pub fn step(&mut self, mut rule: impl FnMut(&T, &[T]) -> T) -> usize {
+        let Automaton { cells, buffer, neighborhood, boundary, .. } = self;
+        let grow = match boundary {
+            Boundary::Infinite(_) => neighborhood.reach(),
+            _ => 0,
+        };
+        let (width, height) = (cells.width() + 2 * grow, cells.height() + 2 * grow);
+        if (buffer.width(), buffer.height()) != (width, height) {
+            if let Some(fill) = cells.iter().next() {
+                *buffer = Grid::new(width, height, fill.clone());
+            }
+        }
+
+        let mut changed = 0;
+        let mut neighbors = Vec::with_capacity(neighborhood.offsets().len());
+        for position in buffer.positions() {
+            let (row, col) = (position.0 as isize - grow as isize, position.1 as isize - grow as isize);
+            neighbors.clear();
+            neighbors.extend(neighborhood.offsets().iter().map(|&(dr, dc)| lookup(cells, boundary, row + dr, col + dc).clone()));
+            let current = lookup(cells, boundary, row, col);
+            let next = rule(current, &neighbors);
+            if next != *current { changed += 1 }
+            buffer[position] = next;
+        }
+
+        if let Boundary::Infinite(background) = boundary {
+            let surrounded = vec![background.clone(); neighborhood.offsets().len()];
+            *background = rule(background, &surrounded);
+        }
+
+        swap(cells, buffer);
+        self.generation += 1;
+        changed
+    }
+
+    /// Steps until a step changes nothing, returning the generation at which that happened
+    pub fn run_until_stable(&mut self, mut rule: impl FnMut(&T, &[T]) -> T) -> usize {
+        while self.step(&mut rule) > 0 {}
+        self.generation
+    }
+}
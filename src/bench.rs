@@ -0,0 +1,143 @@
+//! Times parsing and each part of a day many times over, and compares the results with a saved baseline.
+
+use std::fs::{create_dir_all, read_to_string, write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use color_eyre::Report;
+use serde::{Deserialize, Serialize};
+
+use crate::output::format_duration;
+use crate::runner::timed;
+use crate::solver::Solver;
+
+/// A summary of how long something took over a number of runs
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    pub samples: usize,
+    #[serde(with = "nanos")]
+    pub min: Duration,
+    #[serde(with = "nanos")]
+    pub median: Duration,
+    #[serde(with = "nanos")]
+    pub mean: Duration,
+    /// The sample standard deviation
+    #[serde(with = "nanos")]
+    pub stddev: Duration,
+}
+
+/// Durations are kept as whole nanoseconds in baseline files
+mod nanos {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(d: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(d.as_nanos() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        Ok(Duration::from_nanos(u64::deserialize(deserializer)?))
+    }
+}
+
+impl Stats {
+    /// Summarises a set of timings, of which there must be at least one
+    pub fn from_samples(mut samples: Vec<Duration>) -> Stats {
+        assert!(!samples.is_empty(), "Can't summarise no samples");
+        samples.sort();
+        let n = samples.len();
+        let median = if n % 2 == 1 { samples[n / 2] } else { (samples[n / 2 - 1] + samples[n / 2]) / 2 };
+        let mean = samples.iter().sum::<Duration>() / n as u32;
+        let variance = if n < 2 {
+            0.0
+        } else {
+            samples.iter().map(|s| (s.as_secs_f64() - mean.as_secs_f64()).powi(2)).sum::<f64>() / (n - 1) as f64
+        };
+        Stats { samples: n, min: samples[0], median, mean, stddev: Duration::from_secs_f64(variance.sqrt()) }
+    }
+}
+
+/// Timings for parsing and both parts of one day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchResult {
+    pub day: u32,
+    pub input: PathBuf,
+    pub parse: Stats,
+    pub part1: Stats,
+    pub part2: Stats,
+}
+
+impl BenchResult {
+    fn phases(&self) -> [(&'static str, &Stats); 3] {
+        [("parse", &self.parse), ("part 1", &self.part1), ("part 2", &self.part2)]
+    }
+}
+
+/// Parses and solves `input` `warmup` times without timing it, then `samples` more times with timing
+pub fn bench(solver: &dyn Solver, input: &Path, warmup: usize, samples: usize) -> Result<BenchResult, Report> {
+    let text = read_to_string(input)?;
+    let mut times = [vec![], vec![], vec![]];
+
+    for i in 0..warmup + samples.max(1) {
+        let (parsed, parse) = timed(|| solver.parse(&text));
+        let parsed = parsed?;
+        let (answer, part1) = timed(|| solver.part1(&parsed));
+        answer?;
+        let (answer, part2) = timed(|| solver.part2(&parsed));
+        answer?;
+
+        if i >= warmup {
+            for (phase, time) in times.iter_mut().zip([parse, part1, part2]) {
+                phase.push(time);
+            }
+        }
+    }
+
+    let [parse, part1, part2] = times.map(Stats::from_samples);
+    Ok(BenchResult { day: solver.day(), input: input.to_owned(), parse, part1, part2 })
+}
+
+/// How much slower (positive) or faster (negative) `now` is than `before`, as a percentage
+pub fn percent_change(before: Duration, now: Duration) -> f64 {
+    (now.as_secs_f64() - before.as_secs_f64()) / before.as_secs_f64() * 100.0
+}
+
+pub fn load_baseline(path: &Path) -> Result<Option<BenchResult>, Report> {
+    if !path.exists() {
+        return Ok(None)
+    }
+    let text = read_to_string(path)?;
+    Ok(Some(serde_json::from_str(&text).map_err(|e| Report::new(e).wrap_err(format!("Couldn't parse {}", path.display())))?))
+}
+
+pub fn save_baseline(path: &Path, result: &BenchResult) -> Result<(), Report> {
+    if let Some(dir) = path.parent() {
+        create_dir_all(dir)?;
+    }
+    write(path, serde_json::to_string_pretty(result)? + "\n")?;
+    Ok(())
+}
+
+/// Renders the timings as a table, with the change in each median from `baseline` if there is one
+pub fn report(result: &BenchResult, baseline: Option<&BenchResult>) -> String {
+    let mut table = format!("{:<6}  {:>10}  {:>10}  {:>10}  {:>10}  {:>8}\n", "Phase", "Min", "Median", "Mean", "Std dev", "Change");
+    table.push_str(&format!("{}\n", "-".repeat(6 + 4 * 12 + 10)));
+    for (i, (name, stats)) in result.phases().into_iter().enumerate() {
+        let change = match baseline {
+            Some(baseline) => format!("{:+.1}%", percent_change(baseline.phases()[i].1.median, stats.median)),
+            None => String::new(),
+        };
+        let row = format!("{:<6}  {:>10}  {:>10}  {:>10}  {:>10}  {:>8}",
+                          name, format_duration(stats.min), format_duration(stats.median),
+                          format_duration(stats.mean), format_duration(stats.stddev), change);
+        table.push_str(row.trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+/// The conventional place to keep a day's baseline
+pub fn default_baseline(day: u32) -> PathBuf {
+    Path::new("bench").join(format!("day{}.json", day))
+}
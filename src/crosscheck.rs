@@ -0,0 +1,115 @@
+//! Checks the days that take shortcuts against slow, obvious versions of the same thing, on generated inputs
+//! and the examples.
+
+use std::fmt::{Display, Formatter};
+use std::fs::read_to_string;
+use std::path::PathBuf;
+
+use color_eyre::Report;
+use tracing::debug;
+
+use crate::answer::Answer;
+use crate::random::Rng;
+use crate::runner::day_files;
+use crate::solver::{Parsed, Solver};
+
+/// Where an input came from, so that a disagreement can be looked at again
+#[derive(Debug, Clone)]
+pub enum Source {
+    Generated { seed: u64, size: Option<usize> },
+    File(PathBuf),
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Generated { seed, size: Some(size) } => write!(f, "generated with --seed {} --size {}", seed, size),
+            Source::Generated { seed, size: None } => write!(f, "generated with --seed {}", seed),
+            Source::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// A part whose answer isn't what the reference worked out
+#[derive(Debug)]
+pub struct Disagreement {
+    pub day: u32,
+    pub part: u32,
+    pub source: Source,
+    pub answer: String,
+    pub reference: String,
+}
+
+impl Display for Disagreement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "day {} part {} ({}): got {}, but the reference got {}", self.day, self.part, self.source, self.answer, self.reference)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub runs: usize,
+    pub seed: u64,
+    pub size: Option<usize>,
+    pub examples: PathBuf,
+}
+
+/// How many answers were compared with a reference answer, and the ones that didn't match
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub compared: usize,
+    pub disagreements: Vec<Disagreement>,
+}
+
+fn outcome(result: Result<Answer, Report>) -> String {
+    match result {
+        Ok(answer) => answer.to_string(),
+        Err(e) => format!("error: {}", e),
+    }
+}
+
+/// Compares both parts of one input, returning how many of them had a reference answer
+fn compare(solver: &dyn Solver, parsed: &Parsed, source: &Source, summary: &mut Summary) -> usize {
+    let before = summary.compared;
+    for part in [1, 2] {
+        let reference = match solver.reference(parsed, part) {
+            Ok(None) => continue,
+            Ok(Some(answer)) => answer.to_string(),
+            Err(e) => format!("error: {}", e),
+        };
+        let answer = outcome(if part == 1 { solver.part1(parsed) } else { solver.part2(parsed) });
+        debug!("day {} part {} ({}): {} and {}", solver.day(), part, source, answer, reference);
+
+        summary.compared += 1;
+        if answer != reference {
+            summary.disagreements.push(Disagreement { day: solver.day(), part, source: source.clone(), answer, reference });
+        }
+    }
+    summary.compared - before
+}
+
+/// Solves the day's examples, and then `runs` generated inputs, one for each seed from `seed` on, comparing
+/// the answers with the reference answers. A day with no reference for the first generated input is taken
+/// not to have one at all, and isn't given any more.
+pub fn crosscheck(solver: &dyn Solver, options: &Options) -> Result<Summary, Report> {
+    let mut summary = Summary::default();
+
+    for path in day_files(&options.examples, solver.day())? {
+        // Some examples are there to show what a bad input does, and there's nothing to compare for those
+        let Ok(parsed) = solver.parse(&read_to_string(&path)?) else { continue };
+        compare(solver, &parsed, &Source::File(path), &mut summary);
+    }
+
+    for run in 0..options.runs {
+        let seed = options.seed + run as u64;
+        let input = solver.generate(&mut Rng::new(seed), options.size);
+        let parsed = solver.parse(&input)
+            .map_err(|e| e.wrap_err(format!("The input generated for day {} with seed {} doesn't parse", solver.day(), seed)))?;
+        let compared = compare(solver, &parsed, &Source::Generated { seed, size: options.size }, &mut summary);
+        if run == 0 && compared == 0 {
+            break
+        }
+    }
+
+    Ok(summary)
+}
@@ -1,24 +1,51 @@
 use color_eyre::Report;
 use itertools::Itertools;
-use tracing::info;
+use nom::character;
+use crate::answer::Answer;
+use crate::parse::{lines, parse_all};
+use crate::random::Rng;
+use crate::solver::Puzzle;
 
-pub(crate) fn solve(input: String) -> Result<(), Report> {
+pub struct Day1;
 
-    let numbers: Vec<u32> = input.trim().split('\n').map(|l| l.parse::<u32>()).collect::<Result<Vec<_>, _>>()?;
-    let part1 = numbers.iter()
-        .tuple_windows()
-        .filter(|(a, b)| b > a)
-        .count();
+impl Puzzle for Day1 {
+    type Input = Vec<u32>;
 
-    info!(day=1, part=1, answer=part1);
+    const DAY: u32 = 1;
+    const TITLE: &'static str = "Sonar Sweep";
 
-    let part2 = numbers.iter()
-        .tuple_windows()
-        .tuple_windows()
-        .filter(|((&a1, &b1, &c1), (&a2, &b2, &c2))| a1 + b1 + c1 < a2 + b2 + c2)
-        .count();
+    fn parse(input: &str) -> Result<Vec<u32>, Report> {
+        Ok(parse_all(input, lines(character::complete::u32))?)
+    }
 
-    info!(day=1, part=2, answer=part2);
+    fn part1(numbers: &Vec<u32>) -> Result<Answer, Report> {
+        let part1 = numbers.iter()
+            .tuple_windows()
+            .filter(|(a, b)| b > a)
+            .count();
 
-    Ok(())
+        Ok(part1.into())
+    }
+
+    fn part2(numbers: &Vec<u32>) -> Result<Answer, Report> {
+        // Neighbouring windows share their middle two numbers, so only the ends need comparing, which
+        // also means nothing gets added up that could overflow
+        let part2 = numbers.iter()
+            .tuple_windows()
+            .filter(|(a, _, _, d)| d > a)
+            .count();
+
+        Ok(part2.into())
+    }
+
+    fn generate(rng: &mut Rng, size: Option<usize>) -> String {
+        // The sea floor mostly gets deeper, a little at a time
+        let mut depth = rng.between(100, 200);
+        let mut text = String::new();
+        for _ in 0..size.unwrap_or(2000) {
+            text += &format!("{}\n", depth);
+            depth = (depth + rng.between(-10, 20)).max(0);
+        }
+        text
+    }
 }
@@ -1,6 +1,11 @@
 use color_eyre::eyre::eyre;
 use color_eyre::Report;
-use tracing::{debug, info};
+use nom::bytes::complete::is_a;
+use tracing::debug;
+use crate::answer::Answer;
+use crate::parse::{lines, parse_all};
+use crate::random::Rng;
+use crate::solver::Puzzle;
 
 #[derive(Debug)]
 enum Opener {
@@ -44,12 +49,14 @@ impl TryInto<Opener> for char {
     }
 }
 
-enum LineParseError {
+pub enum LineParseError {
     UnexpectedCloser(char),
-    UnexpectedEndOfLine(u64)
+    UnexpectedEndOfLine(u64),
+    /// The line is incomplete, but closing it scores more than fits in a u64
+    CompletionTooLong
 }
 
-fn character_score(ch: &char) -> u64 {
+pub fn character_score(ch: &char) -> u64 {
     match ch {
         ')' => 3,
         ']' => 57,
@@ -59,7 +66,7 @@ fn character_score(ch: &char) -> u64 {
     }
 }
 
-fn parse_line(line: &str) -> Result<(), LineParseError> {
+pub fn parse_line(line: &str) -> Result<(), LineParseError> {
     let mut stack: Vec<Opener> = vec![];
     for char in line.chars() {
         if let Ok(opener) = char.try_into() {
@@ -79,39 +86,99 @@ fn parse_line(line: &str) -> Result<(), LineParseError> {
     if stack.is_empty() {
         Ok(())
     } else {
-        let mut score = 0;
         debug!("leftovers: {:?}", stack);
-        stack.reverse();
-        for leftover in stack {
-            score *= 5;
-            score += leftover.closing_score();
+        let score = stack.iter().rev()
+            .try_fold(0u64, |score, leftover| score.checked_mul(5)?.checked_add(leftover.closing_score()));
+        match score {
+            Some(score) => Err(LineParseError::UnexpectedEndOfLine(score)),
+            None => Err(LineParseError::CompletionTooLong),
         }
-        Err(LineParseError::UnexpectedEndOfLine(score))
     }
 }
 
-pub(crate) fn solve(input: String) -> Result<(), Report> {
-    let mut part1_score = 0;
-    let mut part2_scores = vec![];
-    for line in input.split('\n') {
-        match parse_line(line) {
-            Ok(()) => debug!("ok: {}", line),
-            Err(LineParseError::UnexpectedCloser(c)) => {
-                let score = character_score(&c);
-                debug!("bad closer: {} ({} pts)", c, score);
-                part1_score += score;
-            },
-            Err(LineParseError::UnexpectedEndOfLine(score)) => {
-                debug!("incomplete: {} ({} pts)", line, score);
-                part2_scores.push(score);
-            },
+const CHUNKS: [(char, char); 4] = [('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')];
+
+/// A line of chunks that stops before closing them all, or that closes one with the wrong character
+fn random_line(rng: &mut Rng, corrupted: bool) -> String {
+    let length = rng.between(80, 110) as usize;
+    let mut line = String::new();
+    let mut closers = vec![];
+    while line.len() < length || closers.is_empty() {
+        if closers.is_empty() || (closers.len() < 15 && rng.chance(0.55)) {
+            let (opener, closer) = *rng.choose(&CHUNKS);
+            line.push(opener);
+            closers.push(closer);
+        } else {
+            line.extend(closers.pop());
+        }
+    }
+
+    if corrupted {
+        let expected = closers.last().copied();
+        let wrong: Vec<char> = CHUNKS.iter().map(|&(_, closer)| closer).filter(|&c| Some(c) != expected).collect();
+        line.push(*rng.choose(&wrong));
+        // Whatever comes after the first mistake doesn't matter
+        line.extend((0..rng.below(10)).map(|_| *rng.choose(&['(', ')', '[', ']', '{', '}', '<', '>'])));
+    }
+    line
+}
+
+pub struct Day10;
+
+impl Puzzle for Day10 {
+    type Input = Vec<String>;
+
+    const DAY: u32 = 10;
+    const TITLE: &'static str = "Syntax Scoring";
+
+    fn parse(input: &str) -> Result<Vec<String>, Report> {
+        let lines = parse_all(input, lines(is_a("()[]{}<>")))?;
+        Ok(lines.into_iter().map(|line| line.to_owned()).collect())
+    }
+
+    fn part1(lines: &Vec<String>) -> Result<Answer, Report> {
+        let mut part1_score = 0;
+        for line in lines {
+            match parse_line(line) {
+                Ok(()) => debug!("ok: {}", line),
+                Err(LineParseError::UnexpectedCloser(c)) => {
+                    let score = character_score(&c);
+                    debug!("bad closer: {} ({} pts)", c, score);
+                    part1_score += score;
+                },
+                Err(LineParseError::UnexpectedEndOfLine(_) | LineParseError::CompletionTooLong) => {}
+            }
         }
+
+        Ok(part1_score.into())
     }
 
-    info!(day=10, part=1, answer=part1_score);
+    fn part2(lines: &Vec<String>) -> Result<Answer, Report> {
+        let mut part2_scores = vec![];
+        for line in lines {
+            match parse_line(line) {
+                Err(LineParseError::UnexpectedEndOfLine(score)) => {
+                    debug!("incomplete: {} ({} pts)", line, score);
+                    part2_scores.push(score);
+                }
+                Err(LineParseError::CompletionTooLong) => return Err(eyre!("The score for completing {} is too big", line)),
+                _ => {}
+            }
+        }
 
-    part2_scores.sort_unstable();
+        part2_scores.sort_unstable();
 
-    info!(day=10, part=2, answer=part2_scores[part2_scores.len() / 2]);
-    Ok(())
+        if part2_scores.is_empty() {
+            return Err(eyre!("None of the lines are incomplete"))
+        }
+        Ok(part2_scores[part2_scores.len() / 2].into())
+    }
+
+    fn generate(rng: &mut Rng, size: Option<usize>) -> String {
+        // The first line is always incomplete, so that part 2 has something to score
+        (0..size.unwrap_or(100)).map(|i| {
+            let corrupted = i > 0 && rng.chance(0.5);
+            random_line(rng, corrupted) + "\n"
+        }).collect()
+    }
 }
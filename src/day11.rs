@@ -1,85 +1,168 @@
+use color_eyre::eyre::eyre;
 use color_eyre::Report;
-use tracing::{debug, info};
+use tracing::debug;
+use crate::animation::{half_blocks, Simulation};
+use crate::answer::Answer;
+use crate::automaton::{Automaton, Boundary, Neighborhood};
+use crate::grid::Grid;
+use crate::parse::{digit_grid, parse_all};
+use crate::picture::{Color, ColorMap, Picture};
+use crate::random::Rng;
+use crate::solver::Puzzle;
 
-const EDGE: usize = 10;
+/// How long to wait for every octopus to flash together before giving up
+const MAX_STEPS: u64 = 2_000;
 
-fn step(grid: &mut [[i8; EDGE]; EDGE]) -> u64 {
-    for row in grid.iter_mut() {
-        for col in row.iter_mut() {
-            *col += 1
-        }
-    }
+/// An octopus's energy level, and whether it has flashed yet this step
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Octopus {
+    pub energy: u8,
+    pub flashed: bool,
+}
 
-    let mut total_flashes = 0;
-    loop {
-        let mut flashes = 0;
-        for r in 0..EDGE {
-            for c in 0..EDGE {
-                if grid[r][c] == 10 {
-                    flashes += 1;
-
-                    // Increment this cell, even though it's not part of the spec, so we can keep track
-                    // of which have already flashed
-                    grid[r][c] += 1;
-
-                    for dr in &[-1, 0, 1] {
-                        for dc in &[-1, 0, 1] {
-                            let target_r = r as i32 + dr;
-                            let target_c = c as i32 + dc;
-                            if target_r >= 0 && target_r < EDGE as i32 && target_c >= 0 && target_c < EDGE as i32 {
-                                let target_r = target_r as usize;
-                                let target_c = target_c as usize;
-                                if grid[target_r][target_c] < 10 {
-                                    grid[target_r][target_c] += 1;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+impl Octopus {
+    fn is_flashing(&self) -> bool { self.energy > 9 && !self.flashed }
+}
+
+/// The octopuses in `grid`, ready to be stepped
+pub fn octopuses(grid: &Grid<u8>) -> Automaton<Octopus> {
+    // Nothing past the edge of the grid ever flashes
+    let cells = grid.map(|&energy| Octopus { energy, flashed: false });
+    Automaton::new(cells, Neighborhood::Moore, Boundary::Fixed(Octopus::default()))
+}
+
+/// Advances the octopuses one step, returning how many flashed
+pub fn step(octopuses: &mut Automaton<Octopus>) -> u64 {
+    octopuses.step(|o, _| Octopus { energy: o.energy + 1, ..*o });
+
+    // Every octopus charged past 9 flashes once, giving energy to its neighbours, until none are left to flash
+    octopuses.run_until_stable(|o, neighbors| {
+        if o.flashed {
+            *o
+        } else if o.energy > 9 {
+            Octopus { flashed: true, ..*o }
+        } else {
+            let gained = neighbors.iter().filter(|n| n.is_flashing()).count() as u8;
+            Octopus { energy: o.energy + gained, ..*o }
         }
-        if flashes == 0 {
-            break
+    });
+
+    let flashes = octopuses.cells().iter().filter(|o| o.flashed).count();
+    octopuses.step(|o, _| if o.flashed { Octopus::default() } else { *o });
+    flashes as u64
+}
+
+/// Energy levels that one step turns into `after`, if there are any. The octopuses at 0 are the ones that
+/// flashed, which they do in some random order, each with enough energy that the ones before it set it off.
+fn unstep(rng: &mut Rng, after: &Grid<u8>) -> Option<Grid<u8>> {
+    let mut before = Grid::new(after.width(), after.height(), 0);
+    let mut flashed = vec![];
+    for (position, &energy) in after.cells() {
+        if energy == 0 {
+            flashed.push(position);
         } else {
-            total_flashes += flashes;
+            let gained = after.neighbors8(position).filter(|&n| after[n] == 0).count() as u8;
+            before[position] = energy.checked_sub(gained + 1)?;
         }
     }
 
-    for row in grid.iter_mut() {
-        for col in row.iter_mut() {
-            if *col == 11 {
-                *col = 0;
-            }
-        }
+    rng.shuffle(&mut flashed);
+    let mut done = Grid::new(after.width(), after.height(), false);
+    for position in flashed {
+        let gained = after.neighbors8(position).filter(|&n| done[n]).count() as i64;
+        before[position] = rng.between(9 - gained, 9) as u8;
+        done[position] = true;
     }
+    Some(before)
+}
 
-    total_flashes
+/// The octopuses along with their flash counts, for animating
+pub struct Cavern {
+    octopuses: Automaton<Octopus>,
+    steps: usize,
+    flashes: u64,
+    last_flashes: u64,
 }
 
-pub(crate) fn solve(input: String) -> Result<(), Report> {
-    let mut grid = [[0i8; EDGE]; EDGE];
-    for (row, grid_row) in input.split('\n').zip(grid.iter_mut()) {
-        for (index, char) in row.bytes().enumerate() {
-            grid_row[index] = i8::try_from(char - b'0')?;
-        }
+impl Simulation for Cavern {
+    fn step(&mut self) {
+        self.last_flashes = step(&mut self.octopuses);
+        self.flashes += self.last_flashes;
+        self.steps += 1;
+    }
+
+    fn frame(&self) -> String {
+        // The octopuses that just flashed light up; the rest glow as they charge
+        let cells = half_blocks(self.octopuses.cells(), |o| if o.energy == 0 { Color::WHITE } else { Color::heat(o.energy as u32, 12) });
+        format!("{} flashes this step, {} in total\n\n{}", self.last_flashes, self.flashes, cells)
     }
 
-    debug!("{:?}", grid);
+    fn is_finished(&self) -> bool {
+        self.steps > 0 && self.octopuses.cells().iter().all(|o| o.energy == 0)
+    }
+}
+
+pub struct Day11;
+
+impl Puzzle for Day11 {
+    type Input = Grid<u8>;
+
+    const DAY: u32 = 11;
+    const TITLE: &'static str = "Dumbo Octopus";
+
+    fn parse(input: &str) -> Result<Grid<u8>, Report> {
+        let grid = parse_all(input, digit_grid)?;
+
+        debug!("\n{}", grid);
+
+        Ok(grid)
+    }
+
+    fn part1(grid: &Grid<u8>) -> Result<Answer, Report> {
+        let mut octopuses = octopuses(grid);
+        let mut flashes = 0;
+        for _ in 0..100 {
+            flashes += step(&mut octopuses);
+        }
 
-    let mut flashes = 0;
-    for _ in 0..100 {
-        flashes += step(&mut grid);
+        Ok(flashes.into())
     }
 
-    info!(day=11, part=1, answer=flashes);
+    fn part2(grid: &Grid<u8>) -> Result<Answer, Report> {
+        let mut octopuses = octopuses(grid);
+        let mut step_count = 0;
+        while !octopuses.cells().iter().all(|o| o.energy == 0) {
+            if step_count == MAX_STEPS {
+                return Err(eyre!("The octopuses didn't all flash at once in {} steps", MAX_STEPS))
+            }
+            step(&mut octopuses);
+            step_count += 1;
+        }
+
+        Ok(step_count.into())
+    }
 
-    let mut step_count = 100;
-    while !grid.iter().all(|r| r.iter().all(|&c| c == 0)) {
-        step(&mut grid);
-        step_count += 1;
+    fn picture(grid: &Grid<u8>, colors: Option<ColorMap>) -> Result<Option<Picture>, Report> {
+        // Octopuses about to flash are the brightest
+        let colors = colors.unwrap_or(ColorMap::Heat);
+        Ok(Some(Picture::new(grid, |_, &energy| colors.color(energy as u32, 9))))
     }
 
-    info!(day=11, part=2, answer=step_count);
+    fn simulation(grid: &Grid<u8>) -> Result<Option<Box<dyn Simulation>>, Report> {
+        Ok(Some(Box::new(Cavern { octopuses: octopuses(grid), steps: 0, flashes: 0, last_flashes: 0 })))
+    }
 
-    Ok(())
+    fn generate(rng: &mut Rng, size: Option<usize>) -> String {
+        let side = size.unwrap_or(10);
+        // Working back from the step where they all flash means the grid always gets there. Hardly any grids
+        // have another one leading to them though, so that's never more than a few steps.
+        let mut energies = Grid::new(side, side, 0);
+        for _ in 0..MAX_STEPS {
+            match unstep(rng, &energies) {
+                Some(before) => energies = before,
+                None => break,
+            }
+        }
+        energies.render(|&energy| char::from(b'0' + energy))
+    }
 }
@@ -1,15 +1,19 @@
+use color_eyre::eyre::eyre;
 use color_eyre::Report;
+use itertools::Itertools;
 use nom::branch::alt;
 use nom::bytes::complete::tag;
-use nom::character::complete::{alpha1, newline};
-use nom::combinator::{all_consuming, opt};
-use nom::IResult;
-use nom::multi::many1;
+use nom::character::complete::alpha1;
 use nom::sequence::tuple;
-use tracing::{debug, info};
+use tracing::debug;
+use crate::answer::Answer;
+use crate::parse::{lines, parse_all};
+use crate::parse_error::ParseResult;
+use crate::random::Rng;
+use crate::solver::Puzzle;
 
 #[derive(Debug, Eq, PartialEq, Clone)]
-enum Cave {
+pub enum Cave {
     Start,
     End,
     Small(String),
@@ -17,19 +21,19 @@ enum Cave {
 }
 
 impl Cave {
-    fn is_small(&self) -> bool { matches!(self, Cave::Start | Cave::End | Cave::Small(_)) }
+    pub fn is_small(&self) -> bool { matches!(self, Cave::Start | Cave::End | Cave::Small(_)) }
 }
 
 #[derive(Debug)]
-struct Path {
-    from: Cave,
-    to: Cave
+pub struct Path {
+    pub from: Cave,
+    pub to: Cave
 }
 
 #[derive(Debug)]
-struct Trip {
-    caves: Vec<Cave>,
-    can_revisit_small: bool
+pub struct Trip {
+    pub caves: Vec<Cave>,
+    pub can_revisit_small: bool
 }
 
 impl Trip {
@@ -45,7 +49,7 @@ impl Trip {
 
 }
 
-fn cave_parser(i: &str) -> IResult<&str, Cave> {
+fn cave_parser(i: &str) -> ParseResult<'_, Cave> {
     alt((
       tag("start"),
       tag("end"),
@@ -59,18 +63,17 @@ fn cave_parser(i: &str) -> IResult<&str, Cave> {
         }))
 }
 
-fn path_parser(i: &str) -> IResult<&str, Path> {
+fn path_parser(i: &str) -> ParseResult<'_, Path> {
     tuple((
         cave_parser,
         tag("-"),
-        cave_parser,
-        opt(newline)
-    ))(i).map(|(left, (from, _, to, _))|
+        cave_parser
+    ))(i).map(|(left, (from, _, to))|
         (left, Path { from, to })
     )
 }
 
-fn walk(paths: &[Path], can_revisit_small: bool) -> Vec<Trip> {
+pub fn walk(paths: &[Path], can_revisit_small: bool) -> Vec<Trip> {
     let mut trips = vec![Trip { caves: vec![Cave::Start], can_revisit_small }];
     let mut complete_trips = vec![];
 
@@ -108,23 +111,82 @@ fn walk(paths: &[Path], can_revisit_small: bool) -> Vec<Trip> {
     complete_trips
 }
 
-pub(crate) fn solve(input: String) -> Result<(), Report> {
-    let paths = match all_consuming(many1(path_parser))(&input) {
-        Ok(("", paths)) => paths,
-        // This is unreachable because all_consuming returns an error if it doesn't parse the whole string
-        Ok(_) => unreachable!(),
-        Err(e) => return Err(Report::msg(format!("Failed to parse input: {:?}", e)))
-    };
+/// `count` different cave names, none of which could be mistaken for the start or end
+fn cave_names(rng: &mut Rng, count: usize, letters: &[u8]) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    while names.len() < count {
+        let name: String = (0..2).map(|_| char::from(*rng.choose(letters))).collect();
+        if !names.contains(&name) { names.push(name) }
+    }
+    names
+}
+
+pub struct Day12;
+
+impl Puzzle for Day12 {
+    type Input = Vec<Path>;
+
+    const DAY: u32 = 12;
+    const TITLE: &'static str = "Passage Pathing";
 
-    debug!("{:?}", paths);
+    fn parse(input: &str) -> Result<Vec<Path>, Report> {
+        let paths = parse_all(input, lines(path_parser))?;
 
-    let part1_trips = walk(&paths, false);
-    debug!("part 1 trips: {:?}", part1_trips);
-    info!(day=12, part=1, answer=part1_trips.len());
+        debug!("{:?}", paths);
 
-    let part2_trips = walk(&paths, true);
-    debug!("part 2 trips: {:?}", part2_trips);
-    info!(day=12, part=2, answer=part2_trips.len());
+        // Going back and forth between two big caves would never end
+        if let Some(path) = paths.iter().find(|p| !p.from.is_small() && !p.to.is_small()) {
+            return Err(eyre!("Big caves {:?} and {:?} are connected, so there's no end to the paths", path.from, path.to))
+        }
+
+        Ok(paths)
+    }
+
+    fn part1(paths: &Vec<Path>) -> Result<Answer, Report> {
+        let part1_trips = walk(paths, false);
+        debug!("part 1 trips: {:?}", part1_trips);
+
+        Ok(part1_trips.len().into())
+    }
 
-    Ok(())
+    fn part2(paths: &Vec<Path>) -> Result<Answer, Report> {
+        let part2_trips = walk(paths, true);
+        debug!("part 2 trips: {:?}", part2_trips);
+
+        Ok(part2_trips.len().into())
+    }
+
+    fn generate(rng: &mut Rng, size: Option<usize>) -> String {
+        let lowercase: Vec<u8> = (b'a'..=b'z').collect();
+        let uppercase: Vec<u8> = (b'A'..=b'Z').collect();
+        let small = cave_names(rng, size.unwrap_or(6).max(1), &lowercase);
+        let big = cave_names(rng, small.len() / 3 + 1, &uppercase);
+        let caves: Vec<String> = small.iter().chain(&big).cloned().collect();
+
+        // Small caves can lead anywhere, but big caves only lead to small ones
+        let mut paths = vec![];
+        for cave in &small {
+            for _ in 0..rng.between(1, 3) {
+                paths.push((cave.clone(), rng.choose(&caves).clone()));
+            }
+        }
+        for cave in &big {
+            for _ in 0..rng.between(1, 3) {
+                paths.push((cave.clone(), rng.choose(&small).clone()));
+            }
+        }
+        for end in ["start", "end"] {
+            for _ in 0..2 {
+                paths.push((end.to_owned(), rng.choose(&caves).clone()));
+            }
+        }
+
+        let mut paths: Vec<(String, String)> = paths.into_iter()
+            .filter(|(a, b)| a != b)
+            .map(|(a, b)| if a < b { (a, b) } else { (b, a) })
+            .unique()
+            .collect();
+        rng.shuffle(&mut paths);
+        paths.into_iter().map(|(a, b)| if rng.chance(0.5) { format!("{}-{}\n", a, b) } else { format!("{}-{}\n", b, a) }).collect()
+    }
 }
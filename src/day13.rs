@@ -1,160 +1,201 @@
+use color_eyre::eyre::eyre;
 use color_eyre::Report;
-use nom::{character, IResult};
-use nom::branch::alt;
+use fnv::FnvHashSet;
+use itertools::Itertools;
+use nom::character;
 use nom::bytes::complete::tag;
-use nom::character::complete::{newline, one_of, space0, space1};
-use nom::combinator::{all_consuming, opt};
-use nom::multi::many1;
+use nom::character::complete::{one_of, space1};
 use nom::sequence::tuple;
-use tracing::{debug, info};
+use tracing::debug;
+use crate::answer::Answer;
+use crate::geometry::Point2;
+use crate::grid::Grid;
+use crate::parse::{coordinate_pair, lines, parse_all, section_break};
+use crate::parse_error::ParseResult;
+use crate::picture::{ColorMap, Picture};
+use crate::random::Rng;
+use crate::solver::Puzzle;
 use crate::day13::Instruction::{FoldAlongX, FoldAlongY};
 
+/// How far out dots and folds can be; real manuals only go to about 1300, and the paper has to fit in memory
+const MAX_COORDINATE: i32 = 4_095;
+
 #[derive(Debug, Eq, PartialEq)]
-struct Dot {
-    x: usize,
-    y: usize
+pub enum Instruction {
+    FoldAlongX(i32),
+    FoldAlongY(i32)
 }
 
-impl Dot {
-    fn fold(self, i: &Instruction) -> Dot {
-        match i {
-            FoldAlongX(xfold) =>
-            if self.x > *xfold {
-                Dot { x: *xfold - (self.x - *xfold), y: self.y }
-            } else {
-                self
-            }
-            FoldAlongY(yfold) =>
-            if self.y > *yfold {
-                Dot { x: self.x, y : *yfold - (self.y - *yfold)}
-            } else {
-                self
-            }
+impl Instruction {
+    /// Where a dot ends up after folding; dots on the near side of the fold don't move
+    pub fn apply(&self, dot: Point2) -> Point2 {
+        match *self {
+            FoldAlongX(x) if dot.x > x => Point2::new(2 * x - dot.x, dot.y),
+            FoldAlongY(y) if dot.y > y => Point2::new(dot.x, 2 * y - dot.y),
+            _ => dot
         }
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
-enum Instruction {
-    FoldAlongX(usize),
-    FoldAlongY(usize)
-}
-
-#[derive(Debug, Eq, PartialEq)]
-enum Line {
-    Dot(Dot),
-    Instruction(Instruction),
-    Empty
-}
-
-fn dot_parser(i: &str) -> IResult<&str, Line> {
-    tuple((
-        character::complete::i64,
-        space0,
-        tag(","),
-        space0,
-        character::complete::i64,
-        opt(newline)
-        ))(i)
-        .map(|(left, (x, _, _, _, y, _))| (left, Line::Dot(Dot { x: x as usize, y: y as usize })))
+fn dot_parser(i: &str) -> ParseResult<'_, Point2> {
+    coordinate_pair(character::complete::i32)(i)
+        .map(|(left, (x, y))| (left, Point2::new(x, y)))
 }
 
-fn instruction_parser(i: &str) -> IResult<&str, Line> {
+fn instruction_parser(i: &str) -> ParseResult<'_, Instruction> {
     tuple((
         tag("fold along"),
         space1,
         one_of("xy"),
         tag("="),
-        character::complete::i64,
-        opt(newline))
-        )(i)
-        .map(|(left, (_, _, axis, _, coordinate, _))| (left, match axis {
-            'x' => Line::Instruction(FoldAlongX(coordinate as usize)),
-            'y' => Line::Instruction(FoldAlongY(coordinate as usize)),
+        character::complete::i32
+        ))(i)
+        .map(|(left, (_, _, axis, _, coordinate))| (left, match axis {
+            'x' => FoldAlongX(coordinate),
+            'y' => FoldAlongY(coordinate),
             // We said one_of("xy") so we'll only get "x" or "y"
             _ => unreachable!()
         }))
 }
 
-fn empty_line_parser(i: &str) -> IResult<&str, Line> {
-    newline(i).map(|(left, _)| (left, Line::Empty))
-}
-
-fn lines_parser(i: &str) -> IResult<&str, Vec<Line>> {
-    many1(
-        alt((
-            dot_parser,
-            instruction_parser,
-            empty_line_parser
-        ))
-    )(i)
-}
-
-fn fold(dots: Vec<Dot>, instruction: &Instruction) -> Vec<Dot> {
+pub fn fold(dots: Vec<Point2>, instruction: &Instruction) -> Vec<Point2> {
     let mut new_dots = Vec::with_capacity(dots.len());
     for dot in dots {
-        let dot = dot.fold(instruction);
-        if !new_dots.iter().any(|nd| *nd == dot) {
+        let dot = instruction.apply(dot);
+        if !new_dots.contains(&dot) {
             new_dots.push(dot)
         }
     }
     new_dots
 }
 
-fn show(dots: &[Dot]) -> String {
-    let max_x = dots.iter().max_by_key(|d| d.x).map(|d| d.x).unwrap() + 1;
-    let max_y = dots.iter().max_by_key(|d| d.y).map(|d| d.y).unwrap() + 1;
-    let mut s = String::with_capacity((max_x + 1) * max_y);
-    for y in 0..max_y {
-        for x in 0..max_x {
-            if dots.iter().any(|d| d.x == x && d.y == y) {
-                s.push('#');
-            } else {
-                s.push('.');
-            }
+/// The dots as a grid just big enough to hold them all
+pub fn paper(dots: &[Point2]) -> Grid<bool> {
+    let width = dots.iter().map(|d| d.x + 1).max().unwrap_or(0) as usize;
+    let height = dots.iter().map(|d| d.y + 1).max().unwrap_or(0) as usize;
+    let mut paper = Grid::new(width, height, false);
+    for dot in dots {
+        paper[(dot.y as usize, dot.x as usize)] = true;
+    }
+    paper
+}
+
+pub fn show(dots: &[Point2]) -> String {
+    paper(dots).render(|&dot| if dot { '#' } else { '.' })
+}
+
+#[derive(Debug)]
+pub struct Manual {
+    pub dots: Vec<Point2>,
+    pub instructions: Vec<Instruction>
+}
+
+impl Manual {
+    /// The dots left after following every instruction, as long as none of them end up off the paper
+    pub fn folded(&self) -> Result<Vec<Point2>, Report> {
+        let mut dots = self.dots.clone();
+        for i in &self.instructions {
+            dots = fold(dots, i);
+        }
+        match dots.iter().find(|d| d.x < 0 || d.y < 0) {
+            Some(dot) => Err(eyre!("The dot at {:?} is off the edge of the paper", dot)),
+            None => Ok(dots),
         }
-        s.push('\n');
     }
-    s
 }
 
-pub(crate) fn solve(input: String) -> Result<(), Report> {
-    let lines = match all_consuming(lines_parser)(&input) {
-        Ok(("", lines)) => lines,
-        // all_consuming won't return a success with anything left over
-        Ok(_) => unreachable!(),
-        Err(e) => return Err(Report::msg(format!("Failed to parse input: {:?}", e)))
-    };
-
-    debug!("{:?}", lines);
-
-    // There must be a better way to handle multiple data types being parsed from a single file, right?
-    let mut dots = vec![];
-    let mut instructions = vec![];
-    for line in lines {
-        match line {
-            Line::Dot(d) => dots.push(d),
-            Line::Instruction(i) => instructions.push(i),
-            Line::Empty => {}
+pub struct Day13;
+
+impl Puzzle for Day13 {
+    type Input = Manual;
+
+    const DAY: u32 = 13;
+    const TITLE: &'static str = "Transparent Origami";
+
+    fn parse(input: &str) -> Result<Manual, Report> {
+        let (dots, _, instructions) = parse_all(input, tuple((
+            lines(dot_parser),
+            section_break,
+            lines(instruction_parser)
+        )))?;
+
+        debug!("{:?}", dots);
+
+        let on_paper = |c: i32| (0..=MAX_COORDINATE).contains(&c);
+        if let Some(dot) = dots.iter().find(|d| !on_paper(d.x) || !on_paper(d.y)) {
+            return Err(eyre!("The dot at {:?} is outside the paper, which goes from 0 to {}", dot, MAX_COORDINATE))
+        }
+        if let Some(i) = instructions.iter().find(|&i| !matches!(*i, FoldAlongX(c) | FoldAlongY(c) if on_paper(c))) {
+            return Err(eyre!("{:?} is outside the paper, which goes from 0 to {}", i, MAX_COORDINATE))
         }
+
+        Ok(Manual { dots, instructions })
     }
 
-    dots = fold(dots, &instructions.remove(0));
+    fn part1(manual: &Manual) -> Result<Answer, Report> {
+        let first = manual.instructions.first().ok_or_else(|| eyre!("No fold instructions"))?;
+        let dots = fold(manual.dots.clone(), first);
 
-    debug!("{:?}", dots);
+        debug!("{:?}", dots);
 
-    info!(day=13, part=1, answer=dots.len());
+        Ok(dots.len().into())
+    }
 
-    for i in &instructions {
-        dots = fold(dots, i);
+    fn part2(manual: &Manual) -> Result<Answer, Report> {
+        Ok(Answer::grid(&show(&manual.folded()?)))
     }
 
-    // This is a bit hinky, but whatever
-    for row in show(&dots).split('\n') {
-        if !row.is_empty() {
-            info!(day=13, part=2, answer=row);
-        }
+    fn picture(manual: &Manual, colors: Option<ColorMap>) -> Result<Option<Picture>, Report> {
+        // Dots are the low value, so that they come out dark on light paper
+        let colors = colors.unwrap_or(ColorMap::Mono);
+        Ok(Some(Picture::new(&paper(&manual.folded()?), |_, &dot| colors.color(u32::from(!dot), 1))))
     }
 
-    Ok(())
+    fn generate(rng: &mut Rng, size: Option<usize>) -> String {
+        // Working backwards from the folded-up paper, unfolding it along each line in turn
+        let (mut width, mut height) = (40, 6);
+        let mut along_x = [true, true, true, true, true, false, false, false, false, false, false, false];
+        rng.shuffle(&mut along_x);
+        let mut instructions = vec![];
+        for x in along_x {
+            if x {
+                instructions.push(FoldAlongX(width));
+                width = 2 * width + 1;
+            } else {
+                instructions.push(FoldAlongY(height));
+                height = 2 * height + 1;
+            }
+        }
+
+        // Each dot ends up somewhere in the picture on the folded paper, from either side of each fold
+        let picture: Vec<Point2> = (0..40).cartesian_product(0..6)
+            .filter(|_| rng.chance(0.4))
+            .map(|(x, y)| Point2::new(x, y))
+            .collect();
+        let mut dots = FnvHashSet::default();
+        let mut text = String::new();
+        for _ in 0..size.unwrap_or(800) {
+            let mut dot = *rng.choose(&picture);
+            for instruction in &instructions {
+                if rng.chance(0.5) {
+                    dot = match *instruction {
+                        FoldAlongX(x) => Point2::new(2 * x - dot.x, dot.y),
+                        FoldAlongY(y) => Point2::new(dot.x, 2 * y - dot.y),
+                    };
+                }
+            }
+            if dots.insert(dot) {
+                text += &format!("{},{}\n", dot.x, dot.y);
+            }
+        }
+
+        text += "\n";
+        for instruction in instructions.iter().rev() {
+            text += &match instruction {
+                FoldAlongX(x) => format!("fold along x={}\n", x),
+                FoldAlongY(y) => format!("fold along y={}\n", y),
+            };
+        }
+        text
+    }
 }
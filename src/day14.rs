@@ -1,29 +1,34 @@
 use std::collections::HashMap;
+use color_eyre::eyre::eyre;
 use color_eyre::Report;
 use itertools::Itertools;
-use nom::bytes::complete::{tag, take_while};
-use nom::character::complete::{alpha1, newline, space0};
-use nom::IResult;
-use nom::combinator::all_consuming;
-use nom::multi::many1;
+use nom::bytes::complete::tag;
+use nom::character::complete::{alpha1, satisfy, space0};
 use nom::sequence::tuple;
-use tracing::{debug, info};
+use tracing::debug;
+use crate::animation::{bar_chart, Simulation};
+use crate::answer::Answer;
+use crate::parse::{lines, parse_all, section_break};
+use crate::parse_error::ParseResult;
+use crate::random::Rng;
+use crate::solver::Puzzle;
 
-#[derive(Debug)]
-struct Rule {
-    input: (char, char),
-    output: ((char, char), (char, char))
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub input: (char, char),
+    pub output: ((char, char), (char, char))
 }
 
-#[derive(Debug)]
-struct TestCase {
+#[derive(Debug, Clone)]
+pub struct TestCase {
+    template: String,
     rules: Vec<Rule>,
     pairs: HashMap<(char, char), u64>,
     amounts: HashMap<char, u64>
 }
 
 impl TestCase {
-    fn new(template: &str, rules: Vec<Rule>) -> TestCase {
+    pub fn new(template: &str, rules: Vec<Rule>) -> TestCase {
         let mut pairs: HashMap<(char, char), u64> = HashMap::new();
         for char_pair in template.chars().tuple_windows() {
             *pairs.entry(char_pair).or_insert(0) += 1;
@@ -32,10 +37,10 @@ impl TestCase {
         for ch in template.chars() {
             *amounts.entry(ch).or_insert(0) += 1
         }
-        TestCase { rules, pairs, amounts }
+        TestCase { template: template.to_owned(), rules, pairs, amounts }
     }
 
-    fn step(self) -> TestCase {
+    pub fn step(self) -> TestCase {
         let mut new_pairs = HashMap::new();
         let mut amounts = self.amounts;
         for rule in &self.rules {
@@ -45,18 +50,16 @@ impl TestCase {
                 *amounts.entry(rule.output.0.1).or_insert(0) += count;
             }
         }
-        TestCase { rules: self.rules, pairs: new_pairs, amounts}
+        TestCase { template: self.template, rules: self.rules, pairs: new_pairs, amounts}
     }
 
-    fn answer(&self) -> u64 {
+    pub fn answer(&self) -> u64 {
         self.amounts.values().max().unwrap() - self.amounts.values().min().unwrap()
     }
 }
 
 impl Rule {
-    fn from_pair(input: &str, result: &str) -> Rule {
-        let input = input[..2].chars().collect_tuple().unwrap();
-        let (result, ) = result[..1].chars().collect_tuple().unwrap();
+    pub fn new(input: (char, char), result: char) -> Rule {
         Rule {
             input,
             output: ((input.0, result), (result, input.1))
@@ -64,54 +67,153 @@ impl Rule {
     }
 }
 
-fn rule_parser(i: &str) -> IResult<&str, Rule> {
+fn element(i: &str) -> ParseResult<'_, char> {
+    satisfy(|c| c.is_ascii_alphabetic())(i)
+}
+
+fn rule_parser(i: &str) -> ParseResult<'_, Rule> {
     tuple((
-        alpha1,
+        element,
+        element,
         space0,
         tag("->"),
         space0,
-        alpha1,
-        newline
-    ))(i).map(|(left, (input, _, _, _, output, _))|
-          (left, Rule::from_pair(input, output))
+        element
+    ))(i).map(|(left, (first, second, _, _, _, result))|
+          (left, Rule::new((first, second), result))
     )
 }
 
-fn file_parser(i: &str) -> IResult<&str, TestCase> {
-    all_consuming(tuple((
-        take_while(|c: char| c.is_alphabetic()),
-        newline,
-        newline,
-        many1(
-            rule_parser
-        )
-        )))(i)
-        .map(|(left, (template, _, _, rules))| (left, TestCase::new(template, rules)))
+fn file_parser(i: &str) -> ParseResult<'_, TestCase> {
+    tuple((
+        alpha1,
+        section_break,
+        lines(rule_parser)
+        ))(i)
+        .map(|(left, (template, _, rules))| (left, TestCase::new(template, rules)))
+}
+
+/// The polymer as it grows, for animating
+pub struct Polymerization {
+    polymer: TestCase,
+    steps: usize,
+}
+
+impl Simulation for Polymerization {
+    fn step(&mut self) {
+        self.polymer = self.polymer.clone().step();
+        self.steps += 1;
+    }
+
+    fn frame(&self) -> String {
+        let bars: Vec<_> = self.polymer.amounts.iter().sorted().map(|(element, &count)| (element.to_string(), count)).collect();
+        let length: u64 = self.polymer.amounts.values().sum();
+        format!("Step {}: length {}, most minus least common {}\n\n{}", self.steps, length, self.polymer.answer(), bar_chart(&bars, 60))
+    }
+
+    fn is_finished(&self) -> bool { self.steps >= 40 }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![("template", self.polymer.template.clone())]
+    }
+
+    fn set(&mut self, param: &str, value: &str) -> Result<(), Report> {
+        match param {
+            // Starts again from the new template with the same rules
+            "template" if !value.is_empty() && value.chars().all(|c| c.is_ascii_uppercase()) => {
+                self.polymer = TestCase::new(value, self.polymer.rules.clone());
+                self.steps = 0;
+                Ok(())
+            }
+            "template" => Err(eyre!("A template is made of capital letters, not {:?}", value)),
+            _ => Err(eyre!("There's no setting called {}", param)),
+        }
+    }
+}
+
+/// The most common element's count minus the least common's after `steps`, from the whole polymer written
+/// out rather than counts of pairs
+pub fn naive_answer(test_case: &TestCase, steps: usize) -> usize {
+    let insertions: HashMap<(char, char), char> = test_case.rules.iter().map(|r| (r.input, r.output.0.1)).collect();
+    let mut polymer: Vec<char> = test_case.template.chars().collect();
+    for _ in 0..steps {
+        let mut next = vec![polymer[0]];
+        for (&a, &b) in polymer.iter().tuple_windows() {
+            next.extend(insertions.get(&(a, b)));
+            next.push(b);
+        }
+        polymer = next;
+    }
+    let counts = polymer.into_iter().counts();
+    counts.values().max().unwrap() - counts.values().min().unwrap()
 }
 
-pub(crate) fn solve(input: String) -> Result<(), Report> {
-    let mut test_case = match file_parser(&input) {
-        Ok(("", test_case)) => test_case,
-        // all_consuming won't return a success with anything left over
-        Ok(_) => unreachable!(),
-        Err(e) => return Err(Report::msg(format!("Failed to parse input: {:?}", e)))
-    };
+pub struct Day14;
+
+impl Puzzle for Day14 {
+    type Input = TestCase;
 
-    debug!("{:?}", test_case.pairs);
+    const DAY: u32 = 14;
+    const TITLE: &'static str = "Extended Polymerization";
 
-    debug!("{:?}", test_case.amounts);
+    fn parse(input: &str) -> Result<TestCase, Report> {
+        let test_case = parse_all(input, file_parser)?;
+
+        // Two rules for the same pair would insert two elements between them, and the counts would grow
+        // far faster than the polymer does
+        if let Some(rule) = test_case.rules.iter().duplicates_by(|r| r.input).next() {
+            return Err(eyre!("There's more than one rule for {}{}", rule.input.0, rule.input.1))
+        }
+
+        debug!("{:?}", test_case.pairs);
 
-    for _ in 0..10 {
-        test_case = test_case.step();
         debug!("{:?}", test_case.amounts);
+
+        Ok(test_case)
+    }
+
+    fn part1(test_case: &TestCase) -> Result<Answer, Report> {
+        let mut test_case = test_case.clone();
+        for _ in 0..10 {
+            test_case = test_case.step();
+            debug!("{:?}", test_case.amounts);
+        }
+
+        Ok(test_case.answer().into())
+    }
+
+    fn part2(test_case: &TestCase) -> Result<Answer, Report> {
+        let mut test_case = test_case.clone();
+        for _ in 0..40 {
+            test_case = test_case.step();
+        }
+
+        Ok(test_case.answer().into())
     }
 
-    info!(day=14, part=1, answer=test_case.answer());
+    fn simulation(test_case: &TestCase) -> Result<Option<Box<dyn Simulation>>, Report> {
+        Ok(Some(Box::new(Polymerization { polymer: test_case.clone(), steps: 0 })))
+    }
 
-    for _ in 0..30 {
-        test_case = test_case.step();
+    fn reference(test_case: &TestCase, part: u32) -> Result<Option<Answer>, Report> {
+        // The polymer doubles in length every step, so 40 steps won't fit in memory
+        Ok(match part {
+            1 => Some(naive_answer(test_case, 10).into()),
+            _ => None,
+        })
     }
 
-    info!(day=14, part=2, answer=test_case.answer());
-    Ok(())
+    fn generate(rng: &mut Rng, size: Option<usize>) -> String {
+        let mut letters: Vec<char> = ('A'..='Z').collect();
+        rng.shuffle(&mut letters);
+        let elements = &letters[..10];
+        let template: String = (0..size.unwrap_or(20).max(1)).map(|_| *rng.choose(elements)).collect();
+
+        // A rule for every pair, as the real inputs have
+        let mut rules: Vec<String> = elements.iter().cartesian_product(elements)
+            .map(|(a, b)| format!("{}{} -> {}\n", a, b, rng.choose(elements)))
+            .collect();
+        rng.shuffle(&mut rules);
+        template + "\n\n" + &rules.concat()
+    }
 }
@@ -1,91 +1,95 @@
+use color_eyre::eyre::eyre;
 use color_eyre::Report;
-use tracing::info;
+use fnv::FnvHashSet;
+use nom::character::complete::one_of;
+use tracing::info_span;
+use crate::answer::Answer;
+use crate::grid::{Grid, Position};
+use crate::parse::{grid, parse_all};
+use crate::parse_error::ParseResult;
+use crate::pathfinding::{astar, Graph, Route};
+use crate::picture::{ColorMap, Picture};
+use crate::random::{self, Rng};
+use crate::solver::Puzzle;
 
 #[derive(Debug)]
-struct Cave {
-    risks: Vec<Vec<u8>>,
-    costs: Vec<Vec<u64>>,
-    to_consider: Vec<(usize, usize)>
+pub struct Cave {
+    pub risks: Grid<u8>,
 }
 
-impl Cave {
-    fn new(risks: Vec<Vec<u8>>) -> Cave {
-        let mut costs = vec![vec![u64::MAX; risks[0].len()]; risks.len()];
-        costs[0][0] = 0;
-        Cave { risks, costs, to_consider: vec![(0, 0)] }
+impl Graph for Cave {
+    type Node = Position;
+
+    fn neighbors(&self, position: Position) -> impl Iterator<Item = (Position, u64)> {
+        self.risks.neighbors4(position).map(|n| (n, self.risks[n] as u64))
     }
+}
 
-    fn consider(&mut self, row: usize, col: usize, cost: u64) {
-        if self.costs[row][col] > cost {
-            self.costs[row][col] = cost;
-            self.to_consider.push((row, col));
-        }
+impl Cave {
+    pub fn new(risks: Grid<u8>) -> Cave {
+        Cave { risks }
     }
 
-    fn width(&self) -> usize { self.risks[0].len() }
-    fn height(&self) -> usize { self.risks.len() }
+    fn exit(&self) -> Position {
+        (self.risks.height() - 1, self.risks.width() - 1)
+    }
 
-    fn answer(&self) -> u64 {
-        *self.costs.last().unwrap().last().unwrap()
+    /// The least risky route from the top left to the bottom right
+    pub fn safest_route(&self) -> Result<Route<Position>, Report> {
+        let _span = info_span!("search", width = self.risks.width(), height = self.risks.height()).entered();
+        let exit = self.exit();
+        // Every step costs at least 1, so the Manhattan distance never overestimates
+        astar(self, (0, 0), |p| p == exit, |(row, col)| ((exit.0 - row) + (exit.1 - col)) as u64)
+            .ok_or_else(|| eyre!("No route through the cave"))
     }
 
-    fn walk(&mut self) {
-        while let Some((row, col)) = self.to_consider.pop() {
-            let cost = self.costs[row][col];
+    pub fn into_part2_cave(self) -> Cave {
+        // Each tile's risks are one higher than the tile above or to the left, wrapping from 9 back to 1
+        Cave::new(self.risks.tile(5, 5, |&risk, (down, across)| ((risk as usize + down + across - 1) % 9 + 1) as u8))
+    }
+}
 
-            if row > 0 {
-                self.consider(row-1, col, cost + self.risks[row-1][col] as u64);
-            }
+/// Risk levels go from 1 to 9; a 0 would wrap around to 9 in the bigger cave
+fn risk(i: &str) -> ParseResult<'_, u8> {
+    one_of("123456789")(i).map(|(left, c)| (left, c as u8 - b'0'))
+}
 
-            if row < self.height() - 1 {
-                self.consider(row+1, col, cost + self.risks[row+1][col] as u64);
-            }
+pub struct Day15;
 
-            if col > 0 {
-                self.consider(row, col-1, cost + self.risks[row][col-1] as u64);
-            }
+impl Puzzle for Day15 {
+    type Input = Grid<u8>;
 
-            if col < self.width() - 1 {
-                self.consider(row, col+1, cost + self.risks[row][col+1] as u64);
-            }
-        }
-    }
+    const DAY: u32 = 15;
+    const TITLE: &'static str = "Chiton";
 
-    fn into_part2_cave(self) -> Cave {
-        let mut new_risks = self.risks.clone();
-        for row in new_risks.iter_mut() {
-            for i in 0..(self.width() * 4) {
-                let new_risk = row[i] % 9 + 1;
-                row.push(new_risk);
-            }
-        }
-
-        for i in 0..self.height() * 4 {
-            new_risks.push(
-                new_risks[i].iter().map(|r| r % 9 + 1).collect()
-            )
-        }
-
-        Cave::new(new_risks)
+    fn parse(input: &str) -> Result<Grid<u8>, Report> {
+        Ok(parse_all(input, grid(risk))?)
     }
-}
-
-pub(crate) fn solve(input: String) -> Result<(), Report> {
-    let grid: Vec<Vec<u8>> = input.trim().split('\n')
-        .map(|line| line.bytes().map(|b| b - b'0').collect())
-        .collect();
-
-    let mut cave = Cave::new(grid);
 
-    cave.walk();
+    fn part1(grid: &Grid<u8>) -> Result<Answer, Report> {
+        let cave = Cave::new(grid.clone());
 
-    info!(day=15, part=1, answer=cave.answer());
+        Ok(cave.safest_route()?.cost.into())
+    }
 
-    let mut part2_cave = cave.into_part2_cave();
+    fn part2(grid: &Grid<u8>) -> Result<Answer, Report> {
+        let part2_cave = Cave::new(grid.clone()).into_part2_cave();
 
-    part2_cave.walk();
+        Ok(part2_cave.safest_route()?.cost.into())
+    }
 
-    info!(day=15, part=2, answer=part2_cave.answer());
+    fn picture(grid: &Grid<u8>, colors: Option<ColorMap>) -> Result<Option<Picture>, Report> {
+        // The risk levels, with the safest route picked out
+        let colors = colors.unwrap_or(ColorMap::Heat);
+        let cave = Cave::new(grid.clone());
+        let route: FnvHashSet<_> = cave.safest_route()?.nodes.into_iter().collect();
+        Ok(Some(Picture::new(grid, |position, &risk| {
+            if route.contains(&position) { colors.highlight() } else { colors.color(risk as u32, 9) }
+        })))
+    }
 
-    Ok(())
+    fn generate(rng: &mut Rng, size: Option<usize>) -> String {
+        let side = size.unwrap_or(100);
+        random::grid(rng, side, side, |rng| char::from(b'1' + rng.below(9) as u8))
+    }
 }
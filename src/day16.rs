@@ -1,72 +1,214 @@
 use bitvec::prelude::*;
 use nom_bitvec::BSlice;
+use color_eyre::eyre::eyre;
 use color_eyre::Report;
+use itertools::Itertools;
 use nom::IResult;
 use nom::branch::alt;
 use nom::bytes::complete::{tag, take};
-use nom::combinator::all_consuming;
+use nom::character::complete::hex_digit1;
+use nom::combinator::{all_consuming, map, verify};
 use nom::multi::{length_count, length_value, many0};
 use nom::sequence::tuple;
-use tracing::{debug, info};
+use tracing::debug;
+use crate::animation::Simulation;
+use crate::answer::Answer;
+use crate::parse::parse_all;
+use crate::parse_error::ParseError;
+use crate::random::Rng;
+use crate::solver::Puzzle;
 
-#[derive(Debug)]
-struct Packet {
-    version: u8,
-    content: PacketType
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Packet {
+    pub version: u8,
+    pub content: PacketType
 }
 
-const OPERATOR_SUM: u8 = 0;
-const OPERATOR_PRODUCT: u8 = 1;
-const OPERATOR_MINIMUM: u8 = 2;
-const OPERATOR_MAXIMUM: u8 = 3;
-const OPERATOR_GREATER_THAN: u8 = 5;
-const OPERATOR_LESS_THAN: u8 = 6;
-const OPERATOR_EQUAL: u8 = 7;
+pub const OPERATOR_SUM: u8 = 0;
+pub const OPERATOR_PRODUCT: u8 = 1;
+pub const OPERATOR_MINIMUM: u8 = 2;
+pub const OPERATOR_MAXIMUM: u8 = 3;
+pub const OPERATOR_GREATER_THAN: u8 = 5;
+pub const OPERATOR_LESS_THAN: u8 = 6;
+pub const OPERATOR_EQUAL: u8 = 7;
 
 impl Packet {
-    fn sum_of_versions(&self) -> usize {
+    pub fn sum_of_versions(&self) -> usize {
         self.version as usize + match &self.content {
             PacketType::Operator(op) => op.subpackets.iter().map(|p| p.sum_of_versions()).sum(),
             _ => 0
         }
     }
 
-    fn value(&self) -> u64 {
+    pub fn value(&self) -> Result<u64, Report> {
         match &self.content {
-            PacketType::LiteralValue(v) => v.value,
+            PacketType::LiteralValue(v) => Ok(v.value),
             PacketType::Operator(o) => o.value(),
         }
     }
+
+    /// The packet found by following `path`, a subpacket index at each level
+    pub fn find(&self, path: &[usize]) -> Option<&Packet> {
+        path.iter().try_fold(self, |packet, &i| match &packet.content {
+            PacketType::Operator(o) => o.subpackets.get(i),
+            PacketType::LiteralValue(_) => None,
+        })
+    }
+
+    /// Replaces the first operator whose subpackets are all literals with its value, returning false if
+    /// there was nothing left to evaluate
+    pub fn evaluate_innermost(&mut self) -> Result<bool, Report> {
+        let PacketType::Operator(op) = &mut self.content else { return Ok(false) };
+        if op.subpackets.iter().all(|p| matches!(p.content, PacketType::LiteralValue(_))) {
+            let value = op.value()?;
+            self.content = PacketType::LiteralValue(LiteralValuePacket { value });
+            return Ok(true)
+        }
+        for subpacket in &mut op.subpackets {
+            if subpacket.evaluate_innermost()? {
+                return Ok(true)
+            }
+        }
+        Ok(false)
+    }
+
+    /// The hex transmission this packet would arrive as, which parses back into the same packet. Operators
+    /// give the length of their subpackets in bits unless that doesn't fit, in which case they give the count.
+    pub fn encode(&self) -> String {
+        let mut bits = BitVec::<Msb0, u8>::new();
+        self.encode_into(&mut bits);
+        // Transmissions are whole bytes, padded with zeroes
+        while !bits.len().is_multiple_of(8) {
+            bits.push(false);
+        }
+        bits.chunks(4).map(|digit| format!("{:X}", digit.load_be::<u8>())).collect()
+    }
+
+    fn encode_into(&self, bits: &mut BitVec<Msb0, u8>) {
+        push_number(bits, self.version as u64, 3);
+        match &self.content {
+            PacketType::LiteralValue(v) => {
+                push_number(bits, 4, 3);
+                // Groups of four bits, most significant first, each after a bit saying whether there's more
+                let groups = (64 - v.value.leading_zeros() as usize).max(1).div_ceil(4);
+                for group in (0..groups).rev() {
+                    bits.push(group > 0);
+                    push_number(bits, v.value >> (group * 4) & 0xf, 4);
+                }
+            }
+            PacketType::Operator(o) => {
+                push_number(bits, o.type_id as u64, 3);
+                let mut subpackets = BitVec::<Msb0, u8>::new();
+                for subpacket in &o.subpackets {
+                    subpacket.encode_into(&mut subpackets);
+                }
+                if subpackets.len() < 1 << 15 {
+                    bits.push(false);
+                    push_number(bits, subpackets.len() as u64, 15);
+                } else {
+                    bits.push(true);
+                    push_number(bits, o.subpackets.len() as u64, 11);
+                }
+                bits.extend_from_bitslice(&subpackets);
+            }
+        }
+    }
+
+    /// One line per packet, with subpackets indented below their operator
+    pub fn outline(&self) -> String {
+        let mut lines = vec![];
+        self.outline_into(0, &mut lines);
+        lines.join("\n")
+    }
+
+    fn outline_into(&self, depth: usize, lines: &mut Vec<String>) {
+        let kind = match &self.content {
+            PacketType::LiteralValue(_) => "literal",
+            PacketType::Operator(o) => operator_name(o.type_id),
+        };
+        lines.push(format!("{}{} v{} = {}", "  ".repeat(depth), kind, self.version, show_value(self)));
+        if let PacketType::Operator(o) = &self.content {
+            for subpacket in &o.subpackets {
+                subpacket.outline_into(depth + 1, lines);
+            }
+        }
+    }
+}
+
+fn show_value(packet: &Packet) -> String {
+    match packet.value() {
+        Ok(value) => value.to_string(),
+        Err(_) => "too big".to_owned(),
+    }
 }
 
-#[derive(Debug)]
-enum PacketType {
+/// Appends the lowest `width` bits of `n`, most significant first
+fn push_number(bits: &mut BitVec<Msb0, u8>, n: u64, width: usize) {
+    for i in (0..width).rev() {
+        bits.push(n >> i & 1 == 1);
+    }
+}
+
+fn operator_name(type_id: u8) -> &'static str {
+    match type_id {
+        OPERATOR_SUM => "sum",
+        OPERATOR_PRODUCT => "product",
+        OPERATOR_MINIMUM => "minimum",
+        OPERATOR_MAXIMUM => "maximum",
+        OPERATOR_GREATER_THAN => "greater than",
+        OPERATOR_LESS_THAN => "less than",
+        OPERATOR_EQUAL => "equal",
+        _ => "unknown",
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PacketType {
     LiteralValue(LiteralValuePacket),
     Operator(OperatorPacket)
 }
 
-#[derive(Debug)]
-struct LiteralValuePacket {
-    value: u64
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LiteralValuePacket {
+    pub value: u64
 }
 
-#[derive(Debug)]
-struct OperatorPacket {
-    type_id: u8,
-    subpackets: Vec<Packet>
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct OperatorPacket {
+    pub type_id: u8,
+    pub subpackets: Vec<Packet>
 }
 
 impl OperatorPacket {
-    fn value(&self) -> u64 {
+    /// Whether there are enough subpackets to work out a value: comparisons need exactly two, and the rest
+    /// at least one
+    pub fn is_well_formed(&self) -> bool {
+        match self.type_id {
+            OPERATOR_GREATER_THAN | OPERATOR_LESS_THAN | OPERATOR_EQUAL => self.subpackets.len() == 2,
+            _ => !self.subpackets.is_empty(),
+        }
+    }
+
+    /// The operator applied to its subpackets' values, or an error if it doesn't fit in a u64 or the packet
+    /// isn't well formed
+    pub fn value(&self) -> Result<u64, Report> {
+        let values = self.subpackets.iter().map(Packet::value).collect::<Result<Vec<u64>, Report>>()?;
+        let too_big = || eyre!("The {} of {:?} doesn't fit in 64 bits", operator_name(self.type_id), values);
+        let comparison = |compare: fn(u64, u64) -> bool| match values[..] {
+            [a, b] => Ok(u64::from(compare(a, b))),
+            _ => Err(eyre!("A {} packet needs two subpackets, not {}", operator_name(self.type_id), values.len())),
+        };
+        let no_subpackets = || eyre!("A {} packet needs at least one subpacket", operator_name(self.type_id));
+
         match self.type_id {
-            OPERATOR_SUM => self.subpackets.iter().map(|sp| sp.value()).sum(),
-            OPERATOR_PRODUCT => self.subpackets.iter().map(|sp| sp.value()).product(),
-            OPERATOR_MINIMUM => self.subpackets.iter().map(|sp| sp.value()).min().unwrap(),
-            OPERATOR_MAXIMUM => self.subpackets.iter().map(|sp| sp.value()).max().unwrap(),
-            OPERATOR_GREATER_THAN => if self.subpackets[0].value() > self.subpackets[1].value() { 1 } else { 0 },
-            OPERATOR_LESS_THAN => if self.subpackets[0].value() < self.subpackets[1].value() { 1 } else { 0 },
-            OPERATOR_EQUAL => if self.subpackets[0].value() == self.subpackets[1].value() { 1 } else { 0 },
-            _ => panic!("unknown type_id: {}", self.type_id)
+            OPERATOR_SUM => values.iter().try_fold(0u64, |total, &v| total.checked_add(v)).ok_or_else(too_big),
+            OPERATOR_PRODUCT => values.iter().try_fold(1u64, |total, &v| total.checked_mul(v)).ok_or_else(too_big),
+            OPERATOR_MINIMUM => values.iter().copied().min().ok_or_else(no_subpackets),
+            OPERATOR_MAXIMUM => values.iter().copied().max().ok_or_else(no_subpackets),
+            OPERATOR_GREATER_THAN => comparison(|a, b| a > b),
+            OPERATOR_LESS_THAN => comparison(|a, b| a < b),
+            OPERATOR_EQUAL => comparison(|a, b| a == b),
+            _ => Err(eyre!("Unknown packet type {}", self.type_id)),
         }
     }
 }
@@ -151,47 +293,168 @@ fn parse_operator_subpackets_by_count(input: BSlice<Msb0, u8>)-> IResult<BSlice<
 }
 
 fn parse_operator_packet(input: BSlice<Msb0, u8>)-> IResult<BSlice<Msb0, u8>, PacketType> {
-    tuple((
-        take(3usize),
-        alt((
-            parse_operator_subpackets_by_total_length,
-            parse_operator_subpackets_by_count
-        ))
-    ))(input)
-        .map(|(left, (type_id, subpackets))|
-            (left, PacketType::Operator(
-                OperatorPacket { type_id: type_id.0.load_be::<u8>(), subpackets }
-            ))
-        )
+    verify(
+        map(
+            tuple((
+                take(3usize),
+                alt((
+                    parse_operator_subpackets_by_total_length,
+                    parse_operator_subpackets_by_count
+                ))
+            )),
+            |(type_id, subpackets)| OperatorPacket { type_id: type_id.0.load_be::<u8>(), subpackets }
+        ),
+        OperatorPacket::is_well_formed
+    )(input)
+        .map(|(left, operator)| (left, PacketType::Operator(operator)))
 }
 
-fn decode_hex(input: &str) -> Vec<u8> {
+/// Decodes pairs of hex digits into bytes; a trailing odd digit is ignored
+pub fn decode_hex(input: &str) -> Result<Vec<u8>, ParseError> {
     let mut v = Vec::with_capacity(input.len() / 2);
+    let mut digits = vec![];
+
+    for (i, c) in input.char_indices() {
+        match c.to_digit(16) {
+            Some(d) => digits.push(d as u8),
+            None => return Err(ParseError::at_offset(input, i, "a hex digit")),
+        }
+    }
+    for pair in digits.chunks_exact(2) {
+        v.push(pair[0] << 4 | pair[1]);
+    }
+
+    Ok(v)
+}
+
+/// The transmission being evaluated one operator at a time, looking at one subpacket of it
+pub struct Evaluation {
+    packet: Packet,
+    path: Vec<usize>,
+    error: Option<String>,
+}
 
-    for i in (0..input.len()-1).step_by(2) {
-        v.push(u8::from_str_radix(&input[i..i+2], 16).unwrap());
+fn show_path(path: &[usize]) -> String {
+    path.iter().join(".")
+}
+
+fn describe_path(path: &[usize]) -> String {
+    if path.is_empty() { "The outermost packet".to_owned() } else { format!("Packet {}", show_path(path)) }
+}
+
+impl Simulation for Evaluation {
+    fn step(&mut self) {
+        // Evaluation stops at the first operator whose value can't be worked out
+        if let Err(e) = self.packet.evaluate_innermost() {
+            self.error = Some(e.to_string());
+        }
     }
 
-    v
+    fn frame(&self) -> String {
+        let frame = match self.packet.find(&self.path) {
+            Some(packet) => format!("{}: value {}, versions add up to {}\n\n{}",
+                                    describe_path(&self.path), show_value(packet), packet.sum_of_versions(), packet.outline()),
+            None => format!("{} has been evaluated into its parent", describe_path(&self.path)),
+        };
+        match &self.error {
+            Some(error) => format!("{}\n\n{}", error, frame),
+            None => frame,
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.error.is_some() || matches!(self.packet.content, PacketType::LiteralValue(_))
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![("path", show_path(&self.path))]
+    }
+
+    fn set(&mut self, param: &str, value: &str) -> Result<(), Report> {
+        if param != "path" {
+            return Err(eyre!("There's no setting called {}", param))
+        }
+        // Dot-separated subpacket indexes, with nothing meaning the outermost packet
+        let path = value.split('.')
+            .filter(|i| !i.is_empty())
+            .map(|i| i.parse().map_err(|_| eyre!("{:?} isn't a subpacket index", i)))
+            .collect::<Result<Vec<usize>, Report>>()?;
+        if self.packet.find(&path).is_none() {
+            return Err(eyre!("There's no packet at {}", value))
+        }
+        self.path = path;
+        Ok(())
+    }
 }
 
-pub(crate) fn solve(input: String) -> Result<(), Report> {
-    let v = decode_hex(&input);
-    let bits = v.view_bits::<Msb0>();
-    let packet = match all_consuming(
-        tuple((
-                  parse_packet,
-                  // Trailing 0 bits are okay
-                  many0(tag(BSlice(bits![0]))),
-        )))(BSlice(bits)) {
-        Ok((slice, (packet, _))) if slice.0.is_empty() => packet,
-        // all_consuming() won't return Ok if it doesn't consume all the data
-        Ok(_) => unreachable!(),
-        Err(e) => return Err(Report::msg(format!("Failed to parse input: {:?}", e)))
-    };
-
-    info!(day=16, part=1, answer=packet.sum_of_versions());
-    info!(day=16, part=2, answer=packet.value());
-
-    Ok(())
+/// A packet made of about `size` packets in all, whose value fits in a u64
+pub fn random_packet(rng: &mut Rng, size: usize) -> Packet {
+    let version = rng.below(8) as u8;
+    if size <= 1 {
+        return Packet { version, content: PacketType::LiteralValue(LiteralValuePacket { value: rng.below(4096) as u64 }) }
+    }
+
+    let mut type_id = *rng.choose(&[OPERATOR_SUM, OPERATOR_PRODUCT, OPERATOR_MINIMUM, OPERATOR_MAXIMUM,
+                                    OPERATOR_GREATER_THAN, OPERATOR_LESS_THAN, OPERATOR_EQUAL]);
+    // Comparisons need exactly two subpackets, which there mightn't be room for
+    let comparison = matches!(type_id, OPERATOR_GREATER_THAN | OPERATOR_LESS_THAN | OPERATOR_EQUAL);
+    let count = if comparison && size >= 3 { 2 } else { rng.between(1, 5) as usize }.min(size - 1);
+    if comparison && count != 2 { type_id = OPERATOR_SUM }
+
+    // The rest of the packets are shared out between the subpackets
+    let subpackets: Vec<Packet> = (0..count)
+        .map(|i| random_packet(rng, (size - 1) / count + usize::from(i < (size - 1) % count)))
+        .collect();
+    let mut operator = OperatorPacket { type_id, subpackets };
+    // A sum or product too big for a u64 has no answer, but the biggest of the same values always fits
+    if operator.value().is_err() { operator.type_id = OPERATOR_MAXIMUM }
+    Packet { version, content: PacketType::Operator(operator) }
+}
+
+pub struct Day16;
+
+impl Puzzle for Day16 {
+    type Input = Packet;
+
+    const DAY: u32 = 16;
+    const TITLE: &'static str = "Packet Decoder";
+
+    fn parse(input: &str) -> Result<Packet, Report> {
+        let v = decode_hex(parse_all(input, hex_digit1)?)?;
+        let bits = v.view_bits::<Msb0>();
+        let packet = match all_consuming(
+            tuple((
+                      parse_packet,
+                      // Trailing 0 bits are okay
+                      many0(tag(BSlice(bits![0]))),
+            )))(BSlice(bits)) {
+            Ok((slice, (packet, _))) if slice.0.is_empty() => packet,
+            // all_consuming() won't return Ok if it doesn't consume all the data
+            Ok(_) => unreachable!(),
+            Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+                // Each hex digit is four bits, so this points at the digit where the packet went wrong
+                let offset = (bits.len() - e.input.0.len()) / 4;
+                return Err(ParseError::at_offset(input, offset, "a valid packet").into())
+            }
+            Err(nom::Err::Incomplete(_)) => return Err(ParseError::at_offset(input, input.len(), "more packet data").into()),
+        };
+
+        Ok(packet)
+    }
+
+    fn part1(packet: &Packet) -> Result<Answer, Report> {
+        Ok(packet.sum_of_versions().into())
+    }
+
+    fn part2(packet: &Packet) -> Result<Answer, Report> {
+        Ok(packet.value()?.into())
+    }
+
+    fn simulation(packet: &Packet) -> Result<Option<Box<dyn Simulation>>, Report> {
+        Ok(Some(Box::new(Evaluation { packet: packet.clone(), path: vec![], error: None })))
+    }
+
+    fn generate(rng: &mut Rng, size: Option<usize>) -> String {
+        random_packet(rng, size.unwrap_or(300)).encode() + "\n"
+    }
 }
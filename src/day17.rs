@@ -1,67 +1,79 @@
 use color_eyre::eyre::eyre;
 use color_eyre::Report;
 use nom::bytes::complete::tag;
-use nom::{character, IResult};
-use nom::character::complete::newline;
-use nom::combinator::{all_consuming, opt};
+use nom::character;
 use nom::sequence::tuple;
-use tracing::{debug, info};
-
-#[derive(Debug)]
-struct TargetArea {
-    x1: i32,
-    x2: i32,
-    y1: i32,
-    y2: i32
-}
-
-impl TargetArea {
-    fn contains(&self, x: i32, y: i32) -> bool {
-        self.x1 <= x && x <= self.x2 && self.y1 <= y && y <= self.y2
-    }
-
-    fn part1_x_velocity(&self) -> Result<i32, Report> {
-        let mut total = 0;
-        for x in 0..100 {
-            total += x;
-            if total >= self.x1 && total <= self.x2 { return Ok(x) }
-        }
-        Err(eyre!("No valid X velocity found"))
+use tracing::debug;
+use crate::answer::Answer;
+use crate::geometry::{Point2, Rect};
+use crate::parse::parse_all;
+use crate::parse_error::ParseResult;
+use crate::random::Rng;
+use crate::solver::Puzzle;
+
+/// The fastest the probe is ever launched, in either direction
+const MAX_SPEED: i32 = 2000;
+
+/// The smallest x velocity that leaves the probe falling straight down inside the target area
+pub fn part1_x_velocity(target: &Rect) -> Result<i32, Report> {
+    let mut total = 0;
+    for x in 0..100 {
+        total += x;
+        if total >= target.min.x && total <= target.max.x { return Ok(x) }
     }
+    Err(eyre!("No valid X velocity found"))
 }
 
 #[derive(Debug)]
-struct Probe {
-    x: i32,
-    y: i32,
-    vel_x: i32,
-    vel_y: i32,
+pub struct Probe {
+    pub position: Point2,
+    pub velocity: Point2,
 }
 
 impl Probe {
-    fn fire(vel_x: i32, vel_y: i32) -> Probe {
+    pub fn fire(velocity: Point2) -> Probe {
         Probe {
-            x: 0,
-            y: 0,
-            vel_x,
-            vel_y
+            position: Point2::ORIGIN,
+            velocity
         }
     }
 }
 
 impl Iterator for Probe {
-    type Item = (i32, i32);
+    type Item = Point2;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.x += self.vel_x;
-        self.y += self.vel_y;
-        self.vel_x -= self.vel_x.signum();
-        self.vel_y -= 1;
-        Some((self.x, self.y))
+        self.position += self.velocity;
+        // Drag pulls x velocity towards 0 and gravity pulls y velocity down
+        self.velocity -= Point2::new(self.velocity.x.signum(), 1);
+        Some(self.position)
+    }
+}
+
+/// Every starting velocity that hits the target, with the highest point the probe reaches on the way,
+/// found by firing at every velocity that could
+pub fn naive_hits(target: &Rect) -> Vec<(Point2, i32)> {
+    let mut hits = vec![];
+    // Any faster to the right and the probe passes the target on the first step; any faster up and it comes
+    // back down past the target between two steps
+    for vel_x in 0..=target.max.x {
+        for vel_y in target.min.y..=-target.min.y {
+            let mut max_y = 0;
+            for position in Probe::fire(Point2::new(vel_x, vel_y)) {
+                max_y = max_y.max(position.y);
+                if target.contains(position) {
+                    hits.push((Point2::new(vel_x, vel_y), max_y));
+                    break
+                } else if position.x > target.max.x || position.y < target.min.y {
+                    break
+                }
+            }
+        }
     }
+    hits
 }
 
-fn parse_target_area(i: &str) -> IResult<&str, TargetArea> {
+fn parse_target_area(i: &str) -> ParseResult<'_, Rect> {
     tuple((
         tag("target area: x="),
         character::complete::i32,
@@ -70,54 +82,91 @@ fn parse_target_area(i: &str) -> IResult<&str, TargetArea> {
         tag(", y="),
         character::complete::i32,
         tag(".."),
-        character::complete::i32,
-        opt(newline)
-    ))(i).map(|(left, (_, x1, _, x2, _, y1, _, y2, _))| (left, TargetArea { x1, x2, y1, y2 }))
+        character::complete::i32
+    ))(i).map(|(left, (_, x1, _, x2, _, y1, _, y2))| (left, Rect::from_corners(Point2::new(x1, y1), Point2::new(x2, y2))))
 }
 
-pub(crate) fn solve(input: String) -> Result<(), Report> {
-    let target = match all_consuming(parse_target_area)(&input) {
-        Ok((_, target)) => target,
-        Err(e) => return Err(Report::msg(format!("Failed to parse input: {:?}", e)))
-    };
-
-    debug!("{:?}", target);
-
-    let vel_x = target.part1_x_velocity()?;
-
-    let mut overall_max_y = 0;
-    for vel_y in 1..2000 {
-        let mut max_y = 0;
-        for (x, y) in Probe::fire(vel_x, vel_y) {
-            if y > max_y { max_y = y }
-            if target.contains(x, y) {
-                if max_y > overall_max_y {
-                    debug!("new max y: {} @ {}, {}", max_y, vel_x, vel_y);
-                    overall_max_y = max_y;
-                }
-            } else if x > target.x2 || y < target.y1 { break }
+pub struct Day17;
+
+impl Puzzle for Day17 {
+    type Input = Rect;
+
+    const DAY: u32 = 17;
+    const TITLE: &'static str = "Trick Shot";
+
+    fn parse(input: &str) -> Result<Rect, Report> {
+        let target = parse_all(input, parse_target_area)?;
+
+        debug!("{:?}", target);
+
+        // The search for velocities only looks right and up to a speed of MAX_SPEED
+        if target.min.x <= 0 || target.max.y >= 0 {
+            return Err(eyre!("The target area has to be below and to the right of the probe"))
         }
+        if target.max.x >= MAX_SPEED || target.min.y <= -MAX_SPEED {
+            return Err(eyre!("The target area has to be within {} of the probe", MAX_SPEED))
+        }
+
+        Ok(target)
     }
 
-    info!(day=17, part=1, answer=overall_max_y);
+    fn part1(target: &Rect) -> Result<Answer, Report> {
+        let vel_x = part1_x_velocity(target)?;
+
+        let mut overall_max_y = 0;
+        for vel_y in 1..MAX_SPEED {
+            let mut max_y = 0;
+            for position in Probe::fire(Point2::new(vel_x, vel_y)) {
+                if position.y > max_y { max_y = position.y }
+                if target.contains(position) {
+                    if max_y > overall_max_y {
+                        debug!("new max y: {} @ {}, {}", max_y, vel_x, vel_y);
+                        overall_max_y = max_y;
+                    }
+                } else if position.x > target.max.x || position.y < target.min.y { break }
+            }
+        }
 
-    let mut possibilities = 0;
+        Ok(overall_max_y.into())
+    }
 
-    for vel_x in 0..=target.x2 {
-        for vel_y in target.y1..2000 {
-            for (x, y) in Probe::fire(vel_x, vel_y) {
-                if target.contains(x, y) {
-                    debug!("possibility: {}, {}", vel_x, vel_y);
-                    possibilities += 1;
-                    break;
-                } else if x > target.x2 || y < target.y1 {
-                    break
+    fn part2(target: &Rect) -> Result<Answer, Report> {
+        let mut possibilities = 0;
+
+        for vel_x in 0..=target.max.x {
+            for vel_y in target.min.y..MAX_SPEED {
+                for position in Probe::fire(Point2::new(vel_x, vel_y)) {
+                    if target.contains(position) {
+                        debug!("possibility: {}, {}", vel_x, vel_y);
+                        possibilities += 1;
+                        break;
+                    } else if position.x > target.max.x || position.y < target.min.y {
+                        break
+                    }
                 }
             }
         }
+
+        Ok(possibilities.into())
     }
 
-    info!(day=17, part=2, answer=possibilities);
+    fn reference(target: &Rect, part: u32) -> Result<Option<Answer>, Report> {
+        let hits = naive_hits(target);
+        Ok(match part {
+            1 => Some(hits.iter().map(|&(_, max_y)| max_y).max().unwrap_or(0).into()),
+            _ => Some(hits.len().into()),
+        })
+    }
 
-    Ok(())
+    fn generate(rng: &mut Rng, size: Option<usize>) -> String {
+        let distance = size.unwrap_or(150).clamp(10, MAX_SPEED as usize - 100) as i64;
+        // There has to be an x velocity that stops the probe above the target
+        let stopping_speed = ((2 * distance) as f64).sqrt() as i64;
+        let stops_at = stopping_speed * (stopping_speed + 1) / 2;
+        let width = rng.between(10, 40);
+        let left = (stops_at - rng.between(0, width)).max(1);
+        let bottom = -rng.between(distance * 2 / 3, distance);
+        let top = (bottom + rng.between(5, 30)).min(-1);
+        format!("target area: x={}..{}, y={}..{}\n", left, left + width, bottom, top)
+    }
 }
@@ -1,26 +1,31 @@
 use std::cell::RefCell;
 use std::fmt::Debug;
 use std::rc::Rc;
-use std::str::FromStr;
 
+use color_eyre::eyre::eyre;
 use color_eyre::Report;
-use nom::IResult;
 use nom::branch::alt;
 use nom::bytes::complete::tag;
-use nom::character::complete::{digit1, newline};
-use nom::combinator::{all_consuming, opt};
-use nom::multi::many1;
-use nom::sequence::{terminated, tuple};
-use tracing::info;
+use nom::character;
+use nom::combinator::{cut, fail};
+use nom::error::context;
+use nom::sequence::tuple;
+use tracing::debug;
+
+use crate::answer::Answer;
+use crate::parse::{lines, parse_all};
+use crate::parse_error::ParseResult;
+use crate::random::Rng;
+use crate::solver::Puzzle;
 
 #[derive(Debug)]
-enum Data {
+pub enum Data {
     RegularNumber(i32),
     Pair(Node, Node),
 }
 
 #[derive(Debug)]
-struct NodeStruct {
+pub struct NodeStruct {
     depth: i32,
     data: Data
 }
@@ -31,7 +36,7 @@ impl NodeStruct {
     }
 }
 
-type Node = Rc<RefCell<NodeStruct>>;
+pub type Node = Rc<RefCell<NodeStruct>>;
 
 struct RegularNodeIterator {
     node: Node,
@@ -90,7 +95,7 @@ impl Iterator for PairNodeIterator {
     }
 }
 
-trait NodeBehavior {
+pub trait NodeBehavior {
     fn get_depth(&self) -> i32;
     fn split_data(&self) -> Data;
     fn add(self, rhs: Node) -> Node;
@@ -153,17 +158,21 @@ impl NodeBehavior for Node {
     }
 }
 
-fn parse_number(depth: i32) -> impl Fn(&str) -> IResult<&str, Node> {
+fn parse_number(depth: i32) -> impl Fn(&str) -> ParseResult<'_, Node> {
     move |i: &str| {
-        digit1(i).map(|(left, n)| {
-            let n = NodeStruct { depth, data: Data::RegularNumber(i32::from_str(n).unwrap()) };
+        character::complete::u8(i).map(|(left, n)| {
+            let n = NodeStruct { depth, data: Data::RegularNumber(n as i32) };
             (left, n.into_node())
         })
     }
 }
 
-fn parse_pair(depth: i32) -> impl Fn(&str) -> IResult<&str, Node> {
+fn parse_pair(depth: i32) -> impl Fn(&str) -> ParseResult<'_, Node> {
     move |i: &str| {
+        // Nothing reduced is nested this deep, and going on would only risk running out of stack
+        if depth > 4 {
+            return cut(context("regular number", fail))(i)
+        }
         tuple((
             tag("["),
             alt((parse_pair(depth+1), parse_number(depth+1))),
@@ -178,25 +187,10 @@ fn parse_pair(depth: i32) -> impl Fn(&str) -> IResult<&str, Node> {
     }
 }
 
-fn parse_input(i: &str) -> IResult<&str, Vec<Node>> {
-    many1(
-        terminated(
-            parse_pair(0),
-            opt(newline)
-        )
-    )(i)
-}
-
-fn print_node(node: &Node) {
+pub fn format_node(node: &Node) -> String {
     match &(**node).borrow().data {
-        Data::RegularNumber(n) => print!("{}", n),
-        Data::Pair(a, b) => {
-            print!("[");
-            print_node(a);
-            print!(",");
-            print_node(b);
-            print!("]");
-        }
+        Data::RegularNumber(n) => n.to_string(),
+        Data::Pair(a, b) => format!("[{},{}]", format_node(a), format_node(b)),
     }
 }
 
@@ -234,7 +228,6 @@ fn try_explode(node: &Node) -> bool {
     if let Some(add) = to_add {
         for n in &mut iter {
             if n.has_value() {
-                // debug!("adding {} to {:?}", add, n);
                 n.set_value(n.get_value() + add);
                 break
             }
@@ -266,8 +259,7 @@ fn try_split(node: &Node) -> bool {
     true
 }
 
-fn reduce(node: &Node) {
-    // print!("reduce:  "); print_node(node); println!();
+pub fn reduce(node: &Node) {
     loop {
         if try_explode(node) {
             continue
@@ -279,7 +271,15 @@ fn reduce(node: &Node) {
     }
 }
 
-fn magnitude(node: &Node) -> i32 {
+/// Whether there's nothing to explode or split
+pub fn is_reduced(node: &Node) -> bool {
+    iterate_node(node).all(|n| match &n.borrow().data {
+        Data::Pair(_, _) => n.get_depth() < 4,
+        Data::RegularNumber(i) => *i < 10,
+    })
+}
+
+pub fn magnitude(node: &Node) -> i32 {
     match &node.borrow().data {
         Data::Pair(a, b) => {
             magnitude(a) * 3 + magnitude(b) * 2
@@ -290,58 +290,65 @@ fn magnitude(node: &Node) -> i32 {
     }
 }
 
-pub(crate) fn solve(input: String) -> Result<(), Report> {
-    let numbers = match all_consuming(parse_input)(&input) {
-        Ok((_, numbers)) => numbers,
-        Err(e) => return Err(Report::msg(format!("Failed to parse input: {:?}", e)))
+/// A snailfish number that's already reduced, whose outermost pair is nested `depth` pairs deep
+fn random_number(rng: &mut Rng, depth: u32) -> String {
+    // A pair inside four others would explode, and a number over 9 would split
+    let element = |rng: &mut Rng| match depth < 3 && rng.chance(0.6) {
+        true => random_number(rng, depth + 1),
+        false => rng.below(10).to_string(),
     };
+    let left = element(rng);
+    format!("[{},{}]", left, element(rng))
+}
+
+pub struct Day18;
+
+impl Puzzle for Day18 {
+    type Input = Vec<Node>;
 
-    // We're going to mess up the nodes in part 1
-    let p2_numbers: Vec<_> = numbers.iter().map(|n| n.deep_copy()).collect();
-
-    let result = numbers.into_iter().reduce(|a, b| {
-        // print!("  ");
-        // print_node(&a);
-        // println!();
-        // print!("+ ");
-        // print_node(&b);
-        // println!();
-        let n = a.add(b);
-        reduce(&n);
-        // print!("= ");
-        // print_node(&n);
-        // println!("\n");
-        n
-    } ).unwrap();
-
-    print_node(&result);
-    println!();
-
-    info!(day=18, part=1, answer=magnitude(&result));
-
-    let mut highest_magnitude = 0;
-    for i in 0..p2_numbers.len() {
-        for j in 0..p2_numbers.len() {
-            if i == j { continue }
-            let total = p2_numbers[i].deep_copy().add(p2_numbers[j].deep_copy());
-            reduce(&total);
-            let mag = magnitude(&total);
-            if mag > highest_magnitude {
-                /*
-                println!("new highest: {}", mag);
-                print_node(&p2_numbers[i]);
-                println!();
-                print_node(&p2_numbers[j]);
-                println!();
-                print_node(&total);
-                println!();
-                println!();
-                */
-                highest_magnitude = mag
+    const DAY: u32 = 18;
+    const TITLE: &'static str = "Snailfish";
+
+    fn parse(input: &str) -> Result<Vec<Node>, Report> {
+        let numbers = parse_all(input, lines(parse_pair(0)))?;
+        // Adding only works on reduced numbers, and the homework never has any that aren't
+        if let Some((line, number)) = numbers.iter().enumerate().find(|(_, n)| !is_reduced(n)) {
+            return Err(eyre!("Line {} isn't reduced: {}", line + 1, format_node(number)))
+        }
+        Ok(numbers)
+    }
+
+    fn part1(numbers: &Vec<Node>) -> Result<Answer, Report> {
+        // Adding and reducing modifies the nodes in place, so work on copies
+        let result = numbers.iter().map(|n| n.deep_copy()).reduce(|a, b| {
+            let n = a.add(b);
+            reduce(&n);
+            n
+        }).ok_or_else(|| eyre!("No snailfish numbers to add"))?;
+
+        debug!("{}", format_node(&result));
+
+        Ok(magnitude(&result).into())
+    }
+
+    fn part2(numbers: &Vec<Node>) -> Result<Answer, Report> {
+        let mut highest_magnitude = 0;
+        for i in 0..numbers.len() {
+            for j in 0..numbers.len() {
+                if i == j { continue }
+                let total = numbers[i].deep_copy().add(numbers[j].deep_copy());
+                reduce(&total);
+                let mag = magnitude(&total);
+                if mag > highest_magnitude {
+                    highest_magnitude = mag
+                }
             }
         }
+
+        Ok(highest_magnitude.into())
     }
 
-    info!(day=18, part=2, answer=highest_magnitude);
-    Ok(())
+    fn generate(rng: &mut Rng, size: Option<usize>) -> String {
+        (0..size.unwrap_or(100)).map(|_| random_number(rng, 0) + "\n").collect()
+    }
 }
@@ -1,158 +1,50 @@
-use std::ops::{Add, Sub};
+use color_eyre::eyre::eyre;
 use color_eyre::Report;
 use nom::bytes::complete::tag;
-use nom::{character, IResult};
+use nom::character;
 use nom::character::complete::newline;
-use nom::combinator::{all_consuming, opt};
-use nom::multi::many1;
+use nom::combinator::verify;
+use nom::error::context;
 use nom::sequence::tuple;
 use fnv::FnvHashSet;
-use tracing::{debug, info};
+use itertools::Itertools;
+use tracing::{debug, info, info_span};
+use crate::answer::Answer;
+use crate::geometry::{Cuboid, Point3, Rotation};
+use crate::parse::{coordinate_triple, lines, parse_all, sections};
+use crate::parse_error::ParseResult;
+use crate::random::Rng;
+use crate::solver::Puzzle;
 
 // 12 sensors in common means any given sensor pair should share 11 neighbors
 const MIN_OVERLAP: usize = 11;
 
-#[derive(Copy, Clone, Debug)]
-#[allow(clippy::upper_case_acronyms)]
-enum Transform {
-    XYZ,
-    XZY,
-    YXZ,
-    YZX,
-    ZXY,
-    ZYX,
-}
-
-const TRANSFORMS: [Transform; 6] = [
-    Transform::XYZ,
-    Transform::XZY,
-    Transform::YXZ,
-    Transform::YZX,
-    Transform::ZXY,
-    Transform::ZYX,
-];
-
-impl Transform {
-    fn apply(&self, x: i32, y: i32, z: i32) -> (i32, i32, i32) {
-        use Transform::*;
-        match self {
-            XYZ => (x, y, z),
-            XZY => (x, z, y),
-            YXZ => (y, x, z),
-            YZX => (y, z, x),
-            ZXY => (z, x, y),
-            ZYX => (z, y, x),
-        }
-    }
-}
-
-#[derive(Copy, Clone, Debug)]
-#[allow(clippy::upper_case_acronyms)]
-enum Flip {
-    None,
-    X,
-    Y,
-    Z,
-    XY,
-    XZ,
-    YZ,
-    XYZ
-}
-
-const FLIPS: [Flip; 8] = [
-    Flip::None,
-    Flip::X,
-    Flip::Y,
-    Flip::Z,
-    Flip::XY,
-    Flip::XZ,
-    Flip::YZ,
-    Flip::XYZ
-];
-
-impl Flip {
-    fn apply(&self, x: i32, y: i32, z: i32) -> (i32, i32, i32) {
-        use Flip::*;
-        match self {
-            None => (x, y, z),
-            X => (-x, y, z),
-            Y => (x, -y, z),
-            Z => (x, y, -z),
-            XY => (-x, -y, z),
-            XZ => (-x, y, -z),
-            YZ => (x, -y, -z),
-            XYZ => (-x, -y, -z)
-        }
-    }
-}
-
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
-struct Beacon(i32, i32, i32);
-
-impl Beacon {
-    fn squared_distance(&self, other: &Beacon) -> i32 {
-        (other.0 - self.0).pow(2) + (other.1 - self.1).pow(2) + (other.2 - self.2).pow(2)
-    }
-
-    /*
-    fn distance(&self, other: &Beacon) -> f64 {
-        (self.squared_distance(other) as f64).sqrt()
-    }
-    */
-
-    fn manhattan_distance(&self, other: &Beacon) -> i32 {
-        (other.0 - self.0).abs() + (other.1 - self.1).abs() + (other.2 - self.2).abs()
-    }
-
-    fn transformed(&self, transform: Transform, flip: Flip) -> Beacon {
-        let (x, y, z) = transform.apply(self.0, self.1, self.2);
-        let (xf, yf, zf) = flip.apply(x, y, z);
-        Beacon(xf, yf, zf)
-    }
-}
-
-impl Sub for Beacon {
-    type Output = Beacon;
-
-    fn sub(self, rhs: Self) -> Self::Output {
-        Beacon(self.0 - rhs.0, self.1 - rhs.1, self.2 - rhs.2)
-    }
-}
-
-impl Add for Beacon {
-    type Output = Beacon;
-
-    fn add(self, rhs: Self) -> Self::Output {
-        Beacon(self.0 + rhs.0, self.1 + rhs.1, self.2 + rhs.2)
-    }
-}
-
-#[derive(Debug)]
-struct Scanner {
-    number: i32,
-    beacons: Vec<Beacon>
+#[derive(Debug, Clone)]
+pub struct Scanner {
+    pub number: i32,
+    pub beacons: Vec<Point3>
 }
 
 impl Scanner {
-    fn beacon_distances(&self) -> Vec<FnvHashSet<i32>> {
+    pub fn beacon_distances(&self) -> Vec<FnvHashSet<i32>> {
         self.beacons.iter()
             .map(|b|
             self.beacons.iter()
                 .filter(|b2| *b2 != b)
-                .map(|b2| b.squared_distance(b2))
+                .map(|b2| b.squared_distance(*b2))
                 .collect()
         ).collect()
     }
 
-    fn transformed(&self, transform: Transform, flip: Flip) -> Scanner {
+    pub fn rotated(&self, rotation: &Rotation) -> Scanner {
         Scanner {
             number: self.number,
-            beacons: self.beacons.iter().map(|b| b.transformed(transform, flip)).collect()
+            beacons: self.beacons.iter().map(|b| rotation.apply(*b)).collect()
         }
     }
 }
 
-fn parse_header(i: &str) -> IResult<&str, i32> {
+fn parse_header(i: &str) -> ParseResult<'_, i32> {
     tuple((
         tag("--- scanner "),
         character::complete::i32,
@@ -161,28 +53,27 @@ fn parse_header(i: &str) -> IResult<&str, i32> {
         ))(i).map(|(left, (_, n, _, _))| (left, n))
 }
 
-fn parse_point(i: &str) -> IResult<&str, Beacon> {
-    tuple((
-        character::complete::i32,
-        tag(","),
-        character::complete::i32,
-        tag(","),
-        character::complete::i32,
-        newline
-    ))(i).map(|(left, (x, _, y, _, z, _))|
-        (left, Beacon(x, y, z)))
+/// How far away a scanner can see beacons, in each direction
+const SCANNER_RANGE: i32 = 1000;
+
+fn parse_point(i: &str) -> ParseResult<'_, Point3> {
+    let in_range = |c: &i32| (-SCANNER_RANGE..=SCANNER_RANGE).contains(c);
+    context(
+        "point within 1000 of the scanner",
+        verify(coordinate_triple(character::complete::i32), move |(x, y, z)| in_range(x) && in_range(y) && in_range(z))
+    )(i)
+        .map(|(left, (x, y, z))| (left, Point3::new(x, y, z)))
 }
 
-fn parse_scanner(i: &str) -> IResult<&str, Scanner> {
+fn parse_scanner(i: &str) -> ParseResult<'_, Scanner> {
     tuple((
         parse_header,
-        many1(parse_point),
-        opt(newline)
-        ))(i).map(|(left, (number, points, _))|
+        lines(parse_point)
+        ))(i).map(|(left, (number, points))|
                           (left, Scanner { number, beacons: points }))
 }
 
-fn find_beacons_in_common(s1: &Scanner, s2: &Scanner) -> Vec<(Beacon, Beacon)> {
+pub fn find_beacons_in_common(s1: &Scanner, s2: &Scanner) -> Vec<(Point3, Point3)> {
     let mut beacons = vec![];
     let s1_distances = s1.beacon_distances();
     let s2_distances = s2.beacon_distances();
@@ -191,85 +82,168 @@ fn find_beacons_in_common(s1: &Scanner, s2: &Scanner) -> Vec<(Beacon, Beacon)> {
             let overlap = s1d.intersection(s2d).count();
             if overlap >= MIN_OVERLAP {
                 beacons.push((s1.beacons[i], s2.beacons[j]));
-                // println!("{:?} - {:?}: {}", s1.beacons[i], s2.beacons[j], overlap);
-                // println!("{:?}", (s1.beacons[i].0 + s2.beacons[j].0, s1.beacons[i].1 - s2.beacons[j].1, s1.beacons[i].2 - (-s2.beacons[j].2)));
             }
         }
     }
     beacons
 }
 
-fn find_transform(base: &Scanner, target: &Scanner) -> Option<(Beacon, Transform, Flip)> {
+/// Finds the rotation and offset that map `target`'s beacons onto `base`'s, if they have enough in common
+pub fn find_transform(base: &Scanner, target: &Scanner) -> Option<(Point3, Rotation)> {
+    let _span = info_span!("find_transform", base = base.number, target = target.number).entered();
     let beacons = find_beacons_in_common(base, target);
     if beacons.is_empty() { return None }
 
-    for transform in &TRANSFORMS {
-        for flip in &FLIPS {
-            let mut matched = true;
-            let offset = beacons[0].0 - beacons[0].1.transformed(*transform, *flip);
-            // debug!("{:?}/{:?}: trying offset {:?}", transform, flip, offset);
-            for (base_beacon, target_beacon) in &beacons[1..] {
-                if *base_beacon - target_beacon.transformed(*transform, *flip) != offset {
-                    matched = false;
-                    break;
-                }
-            }
-            if matched { return Some((offset, *transform, *flip)) }
+    for rotation in Rotation::all() {
+        let offset = beacons[0].0 - rotation.apply(beacons[0].1);
+        if beacons[1..].iter().all(|(base_beacon, target_beacon)| *base_beacon - rotation.apply(*target_beacon) == offset) {
+            return Some((offset, rotation))
         }
     }
     None
 }
 
-pub(crate) fn solve(input: String) -> Result<(), Report> {
-    let mut scanners = match all_consuming(many1(parse_scanner))(&input) {
-        Ok((_, scanners)) => scanners,
-        Err(e) => return Err(Report::msg(format!("Failed to parse input: {:?}", e)))
-    };
+/// Aligns every scanner to the first one, returning each scanner's position along with its transformed beacons
+pub fn align(scanners: &[Scanner]) -> Result<Vec<(Point3, Scanner)>, Report> {
+    let _span = info_span!("align", scanners = scanners.len()).entered();
+    let mut scanners: Vec<Scanner> = scanners.to_vec();
+    if scanners.is_empty() { return Err(eyre!("No scanners to align")) }
 
-    let mut solved_scanners = vec![(Beacon(0, 0, 0), scanners.remove(0))];
+    let mut solved_scanners = vec![(Point3::ORIGIN, scanners.remove(0))];
     while !scanners.is_empty() {
+        // Each round finds one more scanner that overlaps any of the ones already placed
+        let _round = info_span!("match", solved = solved_scanners.len()).entered();
         let mut transformed_scanner = None;
         let mut scanner_pos = None;
 
         'outer: for (pos, solved_scanner) in &solved_scanners {
             for scanner in &scanners {
-                if let Some(xform) = find_transform(solved_scanner, scanner) {
-                    transformed_scanner = Some(scanner.transformed(xform.1, xform.2));
-                    scanner_pos = Some(*pos + xform.0);
+                if let Some((offset, rotation)) = find_transform(solved_scanner, scanner) {
+                    transformed_scanner = Some(scanner.rotated(&rotation));
+                    scanner_pos = Some(*pos + offset);
                     break 'outer;
                 }
             }
         }
 
+        let transformed_scanner = transformed_scanner.ok_or_else(|| eyre!("{} scanners couldn't be aligned", scanners.len()))?;
+
         // Remove the newly-solved scanner from scanners
-        scanners.retain(|s| s.number != transformed_scanner.as_ref().unwrap().number);
+        scanners.retain(|s| s.number != transformed_scanner.number);
 
-        solved_scanners.push((scanner_pos.unwrap(), transformed_scanner.unwrap()));
+        solved_scanners.push((scanner_pos.unwrap(), transformed_scanner));
     }
 
-    let mut all_beacons: FnvHashSet<Beacon> = Default::default();
-    for (pos, scanner) in &solved_scanners {
-        for beacon in &scanner.beacons {
-            all_beacons.insert(*beacon + *pos);
+    Ok(solved_scanners)
+}
+
+/// Where the scanners really are, everything they can see between them, and their reports of it
+#[derive(Debug)]
+pub struct Survey {
+    pub positions: Vec<Point3>,
+    pub beacons: FnvHashSet<Point3>,
+    pub scanners: Vec<Scanner>,
+}
+
+fn random_point(rng: &mut Rng, within: &Cuboid) -> Point3 {
+    let mut coordinate = |min: i32, max: i32| rng.between(min as i64, max as i64) as i32;
+    Point3::new(coordinate(within.min.x, within.max.x), coordinate(within.min.y, within.max.y), coordinate(within.min.z, within.max.z))
+}
+
+/// Scatters `count` scanners so that each one shares at least 12 beacons with one placed before it, and
+/// has each report what it sees facing a random way
+pub fn survey(rng: &mut Rng, count: usize) -> Survey {
+    let range = Point3::new(SCANNER_RANGE, SCANNER_RANGE, SCANNER_RANGE);
+    let view = |position: Point3| Cuboid::from_corners(position - range, position + range);
+    let mut positions: Vec<Point3> = vec![];
+    let mut beacons = FnvHashSet::default();
+
+    for _ in 0..count.max(1) {
+        let position = match positions.is_empty() {
+            true => Point3::ORIGIN,
+            false => {
+                // Close enough to a scanner that's already placed for their views to overlap a good deal
+                let neighbour = *rng.choose(&positions);
+                let mut offset = || rng.between(600, 1200) as i32 * if rng.chance(0.5) { 1 } else { -1 };
+                let position = neighbour + Point3::new(offset(), offset(), offset());
+                let overlap = view(position).intersection(&view(neighbour)).unwrap();
+                let shared = beacons.iter().filter(|&&b| overlap.contains(b)).count();
+                for _ in shared..12 {
+                    while !beacons.insert(random_point(rng, &overlap)) {}
+                }
+                position
+            }
+        };
+        let seen = beacons.iter().filter(|&&b| view(position).contains(b)).count();
+        for _ in seen..25 {
+            while !beacons.insert(random_point(rng, &view(position))) {}
         }
+        positions.push(position);
     }
-    let mut beacons: Vec<_> = all_beacons.into_iter().collect();
-    beacons.sort_unstable();
 
-    for b in &beacons {
-        debug!("{:?}", b);
+    let rotations = Rotation::all();
+    let scanners = positions.iter().enumerate().map(|(number, &position)| {
+        // Everything is lined up with the first scanner, so that one faces the way the map does
+        let facing = if number == 0 { Rotation::IDENTITY } else { *rng.choose(&rotations) };
+        let undo = facing.inverse();
+        let mut seen: Vec<Point3> = beacons.iter().filter(|&&b| view(position).contains(b)).map(|&b| undo.apply(b - position)).collect();
+        seen.sort_unstable();
+        rng.shuffle(&mut seen);
+        Scanner { number: number as i32, beacons: seen }
+    }).collect();
+
+    Survey { positions, beacons, scanners }
+}
+
+pub struct Day19;
+
+impl Puzzle for Day19 {
+    type Input = Vec<Scanner>;
+
+    const DAY: u32 = 19;
+    const TITLE: &'static str = "Beacon Scanner";
+
+    fn parse(input: &str) -> Result<Vec<Scanner>, Report> {
+        Ok(parse_all(input, sections(parse_scanner))?)
     }
 
-    info!(day=19, part=1, answer=beacons.len());
+    fn part1(scanners: &Vec<Scanner>) -> Result<Answer, Report> {
+        let solved_scanners = align(scanners)?;
+
+        let mut all_beacons: FnvHashSet<Point3> = Default::default();
+        for (pos, scanner) in &solved_scanners {
+            for beacon in &scanner.beacons {
+                all_beacons.insert(*beacon + *pos);
+            }
+        }
+        let mut beacons: Vec<_> = all_beacons.into_iter().collect();
+        beacons.sort_unstable();
 
-    let mut max_distance = 0;
-    for i in 0..solved_scanners.len() {
-        for j in i+1..solved_scanners.len() {
-            max_distance = max_distance.max(solved_scanners[i].0.manhattan_distance(&solved_scanners[j].0));
+        for b in &beacons {
+            debug!("{:?}", b);
         }
+
+        Ok(beacons.len().into())
     }
 
-    info!(day=19, part=2, answer=max_distance);
+    fn part2(scanners: &Vec<Scanner>) -> Result<Answer, Report> {
+        let solved_scanners = align(scanners)?;
+
+        let mut max_distance = 0;
+        for i in 0..solved_scanners.len() {
+            for j in i+1..solved_scanners.len() {
+                max_distance = max_distance.max(solved_scanners[i].0.manhattan_distance(solved_scanners[j].0));
+            }
+        }
+
+        Ok(max_distance.into())
+    }
 
-    Ok(())
+    fn generate(rng: &mut Rng, size: Option<usize>) -> String {
+        let survey = survey(rng, size.unwrap_or(30));
+        info!("scanners are at {:?}, and can see {} beacons between them", survey.positions, survey.beacons.len());
+        survey.scanners.iter()
+            .map(|s| format!("--- scanner {} ---\n{}", s.number, s.beacons.iter().map(|b| format!("{},{},{}\n", b.x, b.y, b.z)).collect::<String>()))
+            .join("\n")
+    }
 }
@@ -1,25 +1,30 @@
+use color_eyre::eyre::eyre;
 use color_eyre::Report;
-use nom::{character, IResult};
+use nom::character;
 use nom::branch::alt;
 use nom::bytes::complete::tag;
 use nom::character::complete::multispace1;
 use nom::combinator::complete;
 use nom::error::context;
 use nom::sequence::tuple;
-use tracing::info;
+use crate::answer::Answer;
+use crate::parse::{lines, parse_all};
+use crate::parse_error::ParseResult;
+use crate::random::Rng;
+use crate::solver::Puzzle;
 
 #[derive(Debug)]
-enum Direction {
-    Forward(i32),
-    Up(i32),
-    Down(i32),
+pub enum Direction {
+    Forward(i64),
+    Up(i64),
+    Down(i64),
 }
 
 #[derive(Debug)]
 struct Position {
-    depth: i32,
-    forward: i32,
-    aim: i32
+    depth: i64,
+    forward: i64,
+    aim: i64
 }
 
 impl Position {
@@ -46,12 +51,12 @@ impl Position {
         }
     }
 
-    fn answer(&self) -> i32 {
-        self.depth * self.forward
+    fn answer(&self) -> Result<i64, Report> {
+        self.depth.checked_mul(self.forward).ok_or_else(|| eyre!("The answer is too big"))
     }
 }
 
-fn direction_parser(i: &str) -> IResult<&str, Direction> {
+fn direction_parser(i: &str) -> ParseResult<'_, Direction> {
     complete(
         tuple((
             context(
@@ -59,42 +64,61 @@ fn direction_parser(i: &str) -> IResult<&str, Direction> {
                 alt((tag("forward"), tag("up"), tag("down")))
             ),
             multispace1,
-            character::complete::i32
+            // Small enough that adding them up won't overflow
+            character::complete::u16
         )),
         )(i)
-        .map(|(s, (direction, _, distance))| (s, match direction {
-            "forward" => Direction::Forward(distance),
-            "up" => Direction::Up(distance),
-            "down" => Direction::Down(distance),
+        .map(|(s, (direction, _, distance))| (s, match (direction, distance.into()) {
+            ("forward", distance) => Direction::Forward(distance),
+            ("up", distance) => Direction::Up(distance),
+            ("down", distance) => Direction::Down(distance),
             _ => unreachable!()
         }))
 }
 
-pub(crate) fn solve(input: String) -> Result<(), Report> {
-    let lines: Vec<_> = input.split('\n').collect();
-    let mut directions = Vec::with_capacity(lines.len());
-    for line in lines {
-        match direction_parser(line) {
-            Ok((_, direction)) => directions.push(direction),
-            Err(e) => {
-                return Err(Report::msg(format!("Failed to parse directions: {:?}", e)));
-            }
-        }
-    }
+pub struct Day2;
+
+impl Puzzle for Day2 {
+    type Input = Vec<Direction>;
 
-    let mut position = Position::new();
-    for direction in &directions {
-        position.update(direction)
+    const DAY: u32 = 2;
+    const TITLE: &'static str = "Dive!";
+
+    fn parse(input: &str) -> Result<Vec<Direction>, Report> {
+        Ok(parse_all(input, lines(direction_parser))?)
     }
 
-    info!(day=2, part=1, answer=position.answer());
+    fn part1(directions: &Vec<Direction>) -> Result<Answer, Report> {
+        let mut position = Position::new();
+        for direction in directions {
+            position.update(direction)
+        }
 
-    let mut position = Position::new();
-    for direction in &directions {
-        position.update_part2(direction)
+        Ok(position.answer()?.into())
     }
 
-    info!(day=2, part=2, answer=position.answer());
+    fn part2(directions: &Vec<Direction>) -> Result<Answer, Report> {
+        let mut position = Position::new();
+        for direction in directions {
+            position.update_part2(direction)
+        }
+
+        Ok(position.answer()?.into())
+    }
 
-    Ok(())
+    fn generate(rng: &mut Rng, size: Option<usize>) -> String {
+        // The submarine never aims up out of the water
+        let mut aim = 0;
+        let mut text = String::new();
+        for _ in 0..size.unwrap_or(1000) {
+            let distance = rng.between(1, 9);
+            let direction = match rng.below(3) {
+                0 => "forward",
+                1 if aim >= distance => { aim -= distance; "up" },
+                _ => { aim += distance; "down" },
+            };
+            text += &format!("{} {}\n", direction, distance);
+        }
+        text
+    }
 }
@@ -1,108 +1,135 @@
 use color_eyre::Report;
-use fnv::FnvHashMap;
-use nom::{character, IResult};
+use nom::character;
 use nom::branch::alt;
-use nom::character::complete::newline;
-use nom::combinator::{all_consuming, opt};
-use nom::multi::{count, many1};
+use nom::multi::count;
 use nom::sequence::tuple;
-use tracing::info;
+use tracing::{debug, info_span};
+use crate::animation::{half_blocks, Simulation};
+use crate::answer::Answer;
+use crate::automaton::{Automaton, Boundary, Neighborhood};
+use crate::grid::Grid;
+use crate::parse::{grid, parse_all, section_break};
+use crate::parse_error::ParseResult;
+use crate::picture::{Color, ColorMap, Picture};
+use crate::random::{self, Rng};
+use crate::solver::Puzzle;
 
-fn parse_cell(i: &str) -> IResult<&str, bool> {
+fn parse_cell(i: &str) -> ParseResult<'_, bool> {
     alt((
         character::complete::char('#'),
         character::complete::char('.')
     ))(i).map(|(left, t)| (left, t == '#') )
 }
 
-fn parse_algorithm(i: &str) -> IResult<&str, Vec<bool>> {
+fn parse_input(i: &str) -> ParseResult<'_, (Vec<bool>, Grid<bool>)> {
     tuple((
         count(parse_cell, 512),
-        newline
-    ))(i).map(|(left, (chars, _))| (left, chars))
+        section_break,
+        grid(parse_cell)
+    ))(i).map(|(left, (algorithm, _, image))|
+                      (left, (algorithm, image)))
 }
 
-fn parse_image_line(i: &str) -> IResult<&str, Vec<bool>> {
-    tuple((
-        many1(parse_cell),
-        newline
-    ))(i).map(|(left, (chars, _))| (left, chars))
+fn show(image: &Grid<bool>) -> String {
+    image.render(|&lit| if lit { '#' } else { '.' })
 }
 
-fn parse_input(i: &str) -> IResult<&str, (Vec<bool>, Vec<Vec<bool>>)> {
-    tuple((
-        parse_algorithm,
-        newline,
-        many1(parse_image_line),
-        opt(newline)
-    ))(i).map(|(left, (algorithm, _, image_lines, _))|
-                      (left, (algorithm, image_lines)))
+#[derive(Debug, Clone)]
+pub struct Image {
+    pub algorithm: Vec<bool>,
+    pub image: Grid<bool>
 }
 
-fn calculate_cell(cells: &FnvHashMap<(i32, i32), bool>, coords: (i32, i32), algorithm: &[bool], unset: bool) -> bool {
-    let mut cell_value = 0;
-    for offset in &[(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 0), (0, 1), (1, -1), (1, 0), (1, 1)] {
-        cell_value <<= 1;
-        if *cells.get(&(coords.0 + offset.0, coords.1 + offset.1)).unwrap_or(&unset) { cell_value |= 1 }
+impl Image {
+    /// The image as a window on an infinite expanse of unlit cells, which grows by one cell in each
+    /// direction with every step
+    fn automaton(&self) -> Automaton<bool> {
+        Automaton::new(self.image.clone(), Neighborhood::Block, Boundary::Infinite(false))
     }
-    algorithm[cell_value]
-}
 
-fn show(cells: &FnvHashMap<(i32, i32), bool>) {
-    let top = cells.keys().min_by(|a, b| a.0.cmp(&b.0)).unwrap().0 - 1;
-    let bottom = cells.keys().max_by(|a, b| a.0.cmp(&b.0)).unwrap().0 + 1;
-    let left = cells.keys().min_by(|a, b| a.1.cmp(&b.1)).unwrap().1 - 1;
-    let right = cells.keys().max_by(|a, b| a.1.cmp(&b.1)).unwrap().1 + 1;
-    for row in top..=bottom {
-        for col in left..=right {
-            if *cells.get(&(row, col)).unwrap_or(&false) {
-                print!("#");
-            } else {
-                print!(".");
-            }
+    fn enhance_step(&self, image: &mut Automaton<bool>) {
+        let _span = info_span!("enhance", step = image.generation() + 1).entered();
+        image.step(|_, block| {
+            let index = block.iter().fold(0, |index, &lit| index << 1 | lit as usize);
+            self.algorithm[index]
+        });
+    }
+
+    /// Runs the enhancement algorithm `steps` times, returning the part of the image that's been affected
+    pub fn enhance(&self, steps: usize) -> Grid<bool> {
+        let mut image = self.automaton();
+        debug!("\n{}", show(image.cells()));
+
+        for _ in 0..steps {
+            self.enhance_step(&mut image);
+            debug!("\n{}", show(image.cells()));
         }
-        println!();
+
+        image.cells().clone()
+    }
+
+    /// Runs the enhancement algorithm `steps` times and counts the lit cells
+    pub fn lit_after(&self, steps: usize) -> usize {
+        self.enhance(steps).iter().filter(|c| **c).count()
     }
-    println!();
 }
 
-pub(crate) fn solve(input: String) -> Result<(), Report> {
-    let (algorithm, image_lines) = match all_consuming(parse_input)(&input) {
-        Ok((_, (algorithm, image_lines))) => (algorithm, image_lines),
-        Err(e) => return Err(Report::msg(format!("Failed to parse input: {:?}", e)))
-    };
-    let mut cells = FnvHashMap::default();
-    for (row_num, row) in image_lines.iter().enumerate() {
-        for (col_num, val) in row.iter().enumerate() {
-            cells.insert((row_num as i32, col_num as i32), *val);
-        }
+/// The image as it's enhanced, for animating
+pub struct Enhancement {
+    image: Image,
+    enhanced: Automaton<bool>,
+}
+
+impl Simulation for Enhancement {
+    fn step(&mut self) {
+        self.image.enhance_step(&mut self.enhanced);
     }
-    show(&cells);
-
-    let mut top = 0i32;
-    let mut left = 0i32;
-    let mut bottom = image_lines.len() as i32;
-    let mut right = image_lines[0].len() as i32;
-
-    let mut unset = false;
-    for _ in 0..50 {
-        let mut new_cells = FnvHashMap::default();
-        top -= 1;
-        left -= 1;
-        bottom += 1;
-        right += 1;
-        for row in top..=bottom {
-            for col in left..=right {
-                new_cells.insert((row, col), calculate_cell(&cells, (row, col), &algorithm, unset));
-            }
-        }
-        cells = new_cells;
-        unset = !unset;
-        show(&cells);
+
+    fn frame(&self) -> String {
+        let lit = self.enhanced.cells().iter().filter(|c| **c).count();
+        let cells = half_blocks(self.enhanced.cells(), |&lit| if lit { Color::WHITE } else { Color::new(0x20, 0x20, 0x30) });
+        format!("{} cells lit in view\n\n{}", lit, cells)
     }
 
-    info!(day=20, part=1, answer=cells.values().filter(|c| **c).count());
+    fn is_finished(&self) -> bool { self.enhanced.generation() >= 50 }
+}
 
+pub struct Day20;
 
-    Ok(())
+impl Puzzle for Day20 {
+    type Input = Image;
+
+    const DAY: u32 = 20;
+    const TITLE: &'static str = "Trench Map";
+
+    fn parse(input: &str) -> Result<Image, Report> {
+        let (algorithm, image) = parse_all(input, parse_input)?;
+        Ok(Image { algorithm, image })
+    }
+
+    fn part1(image: &Image) -> Result<Answer, Report> {
+        Ok(image.lit_after(2).into())
+    }
+
+    fn part2(image: &Image) -> Result<Answer, Report> {
+        Ok(image.lit_after(50).into())
+    }
+
+    fn picture(image: &Image, colors: Option<ColorMap>) -> Result<Option<Picture>, Report> {
+        let colors = colors.unwrap_or(ColorMap::Mono);
+        Ok(Some(Picture::new(&image.enhance(50), |_, &lit| colors.color(u32::from(lit), 1))))
+    }
+
+    fn simulation(image: &Image) -> Result<Option<Box<dyn Simulation>>, Report> {
+        Ok(Some(Box::new(Enhancement { image: image.clone(), enhanced: image.automaton() })))
+    }
+
+    fn generate(rng: &mut Rng, size: Option<usize>) -> String {
+        let mut algorithm: Vec<char> = (0..512).map(|_| *rng.choose(&['#', '.'])).collect();
+        // If dark pixels all light up, lit ones all have to go dark again, or infinitely many stay lit
+        if algorithm[0] == '#' { algorithm[511] = '.' }
+
+        let side = size.unwrap_or(100);
+        algorithm.into_iter().collect::<String>() + "\n\n" + &random::grid(rng, side, side, |rng| *rng.choose(&['#', '.']))
+    }
 }
@@ -1,73 +1,123 @@
+use color_eyre::eyre::eyre;
 use color_eyre::Report;
-use tracing::{debug, info};
+use fnv::FnvHashSet;
+use nom::character::complete::one_of;
+use tracing::debug;
+use crate::answer::Answer;
+use crate::parse::{char_grid, parse_all};
+use crate::parse_error::ParseResult;
+use crate::random::Rng;
+use crate::solver::Puzzle;
 
-pub(crate) fn solve(input: String) -> Result<(), Report> {
-    // let numbers = lines.iter().map(|l| u32::from_str_radix(l, 2)).collect::<Result<Vec<_>, _>>()?;
-    let numbers: Vec<Vec<u8>> = input.split('\n').map(|l| l.bytes().map(|c| c - b'0').collect()).collect();
+fn bit(i: &str) -> ParseResult<'_, u8> {
+    one_of("01")(i).map(|(left, c)| (left, c as u8 - b'0'))
+}
+
+pub struct Day3;
+
+impl Puzzle for Day3 {
+    type Input = Vec<Vec<u8>>;
 
-    let mut column_sums = vec![0u32; numbers[0].len()];
-    for number in &numbers {
-        for (&n, sum) in number.iter().zip(column_sums.iter_mut()) {
-            *sum += n as u32;
+    const DAY: u32 = 3;
+    const TITLE: &'static str = "Binary Diagnostic";
+
+    fn parse(input: &str) -> Result<Vec<Vec<u8>>, Report> {
+        let numbers = parse_all(input, char_grid(bit))?;
+        // Multiplying two rates together has to fit in a u64
+        if numbers[0].len() > 32 {
+            return Err(eyre!("The numbers are {} bits long, but can't be longer than 32", numbers[0].len()))
         }
+        Ok(numbers)
     }
 
-    let half = (numbers.len() / 2) as u32;
-
-    let mut gamma = 0;
-    let mut epsilon = 0;
-    for &sum in &column_sums {
-        gamma <<= 1;
-        epsilon <<= 1;
-        if sum > half {
-            gamma |= 1;
-        } else {
-            epsilon |= 1;
+    fn part1(numbers: &Vec<Vec<u8>>) -> Result<Answer, Report> {
+        let mut column_sums = vec![0u32; numbers[0].len()];
+        for number in numbers {
+            for (&n, sum) in number.iter().zip(column_sums.iter_mut()) {
+                *sum += n as u32;
+            }
         }
-    }
 
-    info!(day=3, part=1, gamma=gamma, epsilon=epsilon, answer=gamma*epsilon);
-
-    let mut oxygen_numbers = numbers.clone();
-    for position in 0..numbers[0].len() {
-        let sum: u32 = oxygen_numbers.iter().map(|n| n[position] as u32).sum();
-        let keep = if sum * 2 >= oxygen_numbers.len() as u32 {
-            1
-        } else {
-            0
-        };
-        debug!("in position {}: sum {} of {} so keeping {}", position, sum, oxygen_numbers.len(), keep);
-        oxygen_numbers.retain(|n| n[position] == keep);
-        debug!("now we have {:?}", oxygen_numbers);
-        if oxygen_numbers.len() == 1 {
-            break;
+        let half = (numbers.len() / 2) as u32;
+
+        let mut gamma: u64 = 0;
+        let mut epsilon: u64 = 0;
+        for &sum in &column_sums {
+            gamma <<= 1;
+            epsilon <<= 1;
+            if sum > half {
+                gamma |= 1;
+            } else {
+                epsilon |= 1;
+            }
         }
+
+        debug!(gamma=gamma, epsilon=epsilon);
+
+        Ok((gamma*epsilon).into())
     }
 
-    let mut co2_numbers = numbers.clone();
-    for position in 0..numbers[0].len() {
-        let sum: u32 = co2_numbers.iter().map(|n| n[position] as u32).sum();
-        let keep = if sum * 2 >= co2_numbers.len() as u32 {
-            0
-        } else {
-            1
-        };
-        co2_numbers.retain(|n| n[position] == keep);
-        if co2_numbers.len() == 1 {
-            break;
+    fn part2(numbers: &Vec<Vec<u8>>) -> Result<Answer, Report> {
+        let mut oxygen_numbers = numbers.clone();
+        for position in 0..numbers[0].len() {
+            // Checked before filtering too, since a lone number might not match the bit it's compared with
+            if oxygen_numbers.len() == 1 {
+                break;
+            }
+            let sum: u32 = oxygen_numbers.iter().map(|n| n[position] as u32).sum();
+            let keep = if sum * 2 >= oxygen_numbers.len() as u32 {
+                1
+            } else {
+                0
+            };
+            debug!("in position {}: sum {} of {} so keeping {}", position, sum, oxygen_numbers.len(), keep);
+            oxygen_numbers.retain(|n| n[position] == keep);
+            debug!("now we have {:?}", oxygen_numbers);
         }
-    }
 
-    let mut oxygen: u32 = 0;
-    let mut co2: u32 = 0;
-    for position in 0..numbers[0].len() {
-        oxygen <<= 1;
-        co2 <<= 1;
-        oxygen |= oxygen_numbers[0][position] as u32;
-        co2 |= co2_numbers[0][position] as u32;
-    }
+        let mut co2_numbers = numbers.clone();
+        for position in 0..numbers[0].len() {
+            if co2_numbers.len() == 1 {
+                break;
+            }
+            let sum: u32 = co2_numbers.iter().map(|n| n[position] as u32).sum();
+            // When every number left has the same bit here, there's no less common one to keep instead
+            let keep = if sum == 0 || sum == co2_numbers.len() as u32 {
+                co2_numbers[0][position]
+            } else if sum * 2 >= co2_numbers.len() as u32 {
+                0
+            } else {
+                1
+            };
+            co2_numbers.retain(|n| n[position] == keep);
+        }
+
+        let mut oxygen: u64 = 0;
+        let mut co2: u64 = 0;
+        for position in 0..numbers[0].len() {
+            oxygen <<= 1;
+            co2 <<= 1;
+            oxygen |= oxygen_numbers[0][position] as u64;
+            co2 |= co2_numbers[0][position] as u64;
+        }
 
-    info!(day=3, part=2, oxygen=oxygen, co2=co2, answer=oxygen*co2);
+        debug!(oxygen=oxygen, co2=co2);
 
-    Ok(())
+        Ok((oxygen*co2).into())
+    }
+
+    fn generate(rng: &mut Rng, size: Option<usize>) -> String {
+        let count = size.unwrap_or(1000);
+        // Wide enough that the numbers can all be different, since each rating has to pick out just one
+        let width = (usize::BITS - (4 * count).leading_zeros()).clamp(12, 32);
+        let mut seen = FnvHashSet::default();
+        let mut text = String::new();
+        while seen.len() < count {
+            let number = rng.next_u64() & ((1 << width) - 1);
+            if seen.insert(number) {
+                text += &format!("{:0width$b}\n", number, width = width as usize);
+            }
+        }
+        text
+    }
 }
@@ -1,31 +1,25 @@
+use color_eyre::eyre::eyre;
 use color_eyre::Report;
-use nom::{character, IResult};
-use nom::bytes::complete::tag;
-use nom::character::complete::{newline, space0};
-use nom::combinator::{all_consuming, opt};
-use nom::multi::{count, many1, separated_list1};
-use nom::sequence::{delimited, terminated, tuple};
-use tracing::{debug, info};
-
-fn call_parser(i: &str) -> IResult<&str, Vec<u8>> {
-    terminated(
-        separated_list1(tag(","), character::complete::u8),
-        newline)(i)
-}
+use itertools::Itertools;
+use nom::character;
+use nom::character::complete::space0;
+use nom::multi::count;
+use nom::sequence::{preceded, tuple};
+use tracing::debug;
+use crate::answer::Answer;
+use crate::parse::{comma_separated, lines, parse_all, section_break, sections};
+use crate::parse_error::ParseResult;
+use crate::random::Rng;
+use crate::solver::Puzzle;
 
-fn board_parser(i: &str) -> IResult<&str, Vec<Vec<u8>>> {
-    terminated(
+fn board_parser(i: &str) -> ParseResult<'_, Vec<Vec<u8>>> {
+    lines(
         count(
-        terminated(
-            count(
-                delimited(
-                    space0,
-                    character::complete::u8,
-                    space0
-                ), 5),
-            newline),
-        5),
-    opt(newline))(i)
+            preceded(
+                space0,
+                character::complete::u8
+            ), 5)
+    )(i)
 }
 
 fn is_winning_board(board: &[Vec<u8>]) -> bool {
@@ -49,45 +43,86 @@ fn board_score(board: &[Vec<u8>]) -> u32 {
         .sum()
 }
 
-pub(crate) fn solve(input: String) -> Result<(), Report> {
-    let (calls, mut boards) = match all_consuming(tuple((
-        call_parser,
-        newline,
-        many1(board_parser)
-    )))(&input) {
-        Ok(("", (calls, _, boards))) => (calls, boards),
-        Ok((leftovers, _)) => return Err(Report::msg(format!("Didn't parse all directions: {:?} left", leftovers))),
-        Err(e) => return Err(Report::msg(format!("Failed to parse directions: {:?}", e)))
-    };
-
-    debug!("calls: {:?}", calls);
-
-    let mut found_winner = false;
-
-    for call in calls {
-        for board in boards.iter_mut() {
-            for row in board.iter_mut() {
-                for ch in row.iter_mut() {
-                    if *ch == call { *ch |= 128 }
+#[derive(Debug)]
+pub struct Game {
+    pub calls: Vec<u8>,
+    pub boards: Vec<Vec<Vec<u8>>>
+}
+
+impl Game {
+    /// Plays every call, returning the last call and board score for each board in the order the boards win
+    pub fn play(&self) -> Vec<(u8, u32)> {
+        let mut boards = self.boards.clone();
+        let mut winners = vec![];
+
+        for &call in &self.calls {
+            for board in boards.iter_mut() {
+                for row in board.iter_mut() {
+                    for ch in row.iter_mut() {
+                        if *ch == call { *ch |= 128 }
+                    }
                 }
-            }
 
-            if is_winning_board(board) && !found_winner {
-                debug!("Found winning board: {:?}", board);
-                let score = board_score(board);
-                info!(day=4, part=1, last_called=call, board_score=score, answer=call as u32 * score);
-                found_winner = true;
+                if is_winning_board(board) {
+                    debug!("Found winning board: {:?}", board);
+                    winners.push((call, board_score(board)));
+                }
             }
-        }
 
-        if boards.len() == 1 && is_winning_board(&boards[0]) {
-            debug!("Found last winning board: {:?}", boards[0]);
-            let score = board_score(&boards[0]);
-            info!(day=4, part=2, last_called=call, board_score=score, answer=call as u32 * score);
+            boards.retain(|b| !is_winning_board(b));
         }
 
-        boards.retain(|b| !is_winning_board(b));
+        winners
+    }
+}
+
+pub struct Day4;
+
+impl Puzzle for Day4 {
+    type Input = Game;
+
+    const DAY: u32 = 4;
+    const TITLE: &'static str = "Giant Squid";
+
+    fn parse(input: &str) -> Result<Game, Report> {
+        let (calls, _, boards) = parse_all(input, tuple((
+            comma_separated(character::complete::u8),
+            section_break,
+            sections(board_parser)
+        )))?;
+
+        debug!("calls: {:?}", calls);
+
+        Ok(Game { calls, boards })
+    }
+
+    fn part1(game: &Game) -> Result<Answer, Report> {
+        let (call, score) = *game.play().first().ok_or_else(|| eyre!("No board won"))?;
+        debug!(last_called=call, board_score=score);
+
+        Ok((call as u32 * score).into())
     }
 
-    Ok(())
+    fn part2(game: &Game) -> Result<Answer, Report> {
+        let (call, score) = *game.play().last().ok_or_else(|| eyre!("No board won"))?;
+        debug!(last_called=call, board_score=score);
+
+        Ok((call as u32 * score).into())
+    }
+
+    fn generate(rng: &mut Rng, size: Option<usize>) -> String {
+        // Every number gets called, so every board wins in the end
+        let mut calls: Vec<u8> = (0..100).collect();
+        rng.shuffle(&mut calls);
+        let mut text = calls.iter().join(",") + "\n";
+        for _ in 0..size.unwrap_or(100) {
+            let mut numbers: Vec<u8> = (0..100).collect();
+            rng.shuffle(&mut numbers);
+            text += "\n";
+            for row in numbers[..25].chunks(5) {
+                text += &format!("{}\n", row.iter().map(|n| format!("{:>2}", n)).join(" "));
+            }
+        }
+        text
+    }
 }
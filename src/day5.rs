@@ -1,92 +1,88 @@
 use std::collections::HashMap;
+use color_eyre::eyre::eyre;
 use color_eyre::Report;
 use nom::bytes::complete::tag;
-use nom::{character, IResult};
-use nom::character::complete::{newline, space0, space1};
-use nom::combinator::all_consuming;
-use nom::multi::many1;
-use nom::sequence::{terminated, tuple};
-use tracing::{debug, info};
+use nom::character;
+use nom::character::complete::space1;
+use nom::sequence::tuple;
+use tracing::debug;
+use crate::answer::Answer;
+use crate::geometry::{Point2, Segment};
+use crate::parse::{coordinate_pair, lines, parse_all};
+use crate::parse_error::ParseResult;
+use crate::random::Rng;
+use crate::solver::Puzzle;
 
-#[derive(Debug)]
-struct Line {
-    x1: i32,
-    y1: i32,
-    x2: i32,
-    y2: i32
+fn line_parser(i: &str) -> ParseResult<'_, Segment> {
+    tuple((
+        coordinate_pair(character::complete::u16),
+        space1,
+        tag("->"),
+        space1,
+        coordinate_pair(character::complete::u16)
+    ))(i).map(|(rest, ((x1, y1), _, _, _, (x2, y2)))| {
+        let point = |x: u16, y: u16| Point2::new(x.into(), y.into());
+        (rest, Segment::new(point(x1, y1), point(x2, y2)))
+    })
 }
 
-impl Line {
-    fn new(x1: i32, y1: i32, x2: i32, y2: i32) -> Line {
-        if x1 < x2 || (x1 == x2 && y1 <= y2) {
-            Line { x1, y1, x2, y2 }
-        } else {
-            Line { x1: x2, y1: y2, x2: x1, y2: y1 }
-        }
-    }
+/// Counts the points covered by more than one line, optionally ignoring the diagonal lines
+pub fn overlaps(lines: &[Segment], include_diagonals: bool) -> usize {
+    let mut counts: HashMap<Point2, i32> = HashMap::new();
 
-    fn is_straight(&self) -> bool {
-        self.x1 == self.x2 || self.y1 == self.y2
-    }
-
-    fn covered_coordinates(&self) -> Vec<(i32, i32)> {
-        let mut v = vec![];
-        let mut pos = (self.x1, self.y1);
-        loop {
-            v.push(pos);
-            if pos.0 == self.x2 && pos.1 == self.y2 { break }
-            pos.0 += (self.x2 - pos.0).signum();
-            pos.1 += (self.y2 - pos.1).signum();
+    for line in lines {
+        if !include_diagonals && !line.is_axis_aligned() { continue }
+        debug!("{:?}: {:?}", line, line.points().collect::<Vec<_>>());
+        for point in line.points() {
+            *counts.entry(point).or_insert(0) += 1
         }
-        v
     }
-}
 
-fn pair_parser(i: &str) -> IResult<&str, (i32, i32)> {
-    tuple((
-        character::complete::i32,
-        space0,
-        tag(","),
-        space0,
-        character::complete::i32
-        ))(i).map(|(rest, (x, _, _, _, y))| (rest, (x, y)))
+    counts.values().filter(|&&c| c > 1).count()
 }
 
-fn line_parser(i: &str) -> IResult<&str, Line> {
-    terminated(
-        tuple((
-            pair_parser,
-            space1,
-            tag("->"),
-            space1,
-            pair_parser
-        )),
-        newline
-    )(i).map(|(rest, (p1, _, _, _, p2))| (rest, Line::new(p1.0, p1.1, p2.0, p2.1)))
-}
+/// The ways a line of vents can run: along the grid or at 45 degrees
+const DIRECTIONS: [Point2; 8] = [
+    Point2::new(1, 0), Point2::new(-1, 0), Point2::new(0, 1), Point2::new(0, -1),
+    Point2::new(1, 1), Point2::new(1, -1), Point2::new(-1, 1), Point2::new(-1, -1),
+];
+
+pub struct Day5;
 
-pub(crate) fn solve(input: String) -> Result<(), Report> {
-    let lines = match all_consuming(many1(line_parser))(&input) {
-        Ok(("", lines)) => lines,
-        Ok((leftovers, _)) => return Err(Report::msg(format!("Didn't parse all lines: {:?} left", leftovers))),
-        Err(e) => return Err(Report::msg(format!("Failed to parse input: {:?}", e)))
-    };
+impl Puzzle for Day5 {
+    type Input = Vec<Segment>;
 
-    let mut part1_counts: HashMap<(i32, i32), i32> = HashMap::new();
-    let mut counts: HashMap<(i32, i32), i32> = HashMap::new();
+    const DAY: u32 = 5;
+    const TITLE: &'static str = "Hydrothermal Venture";
 
-    for line in &lines {
-        debug!("{:?}: {:?}", line, line.covered_coordinates());
-        for pair in line.covered_coordinates() {
-            if line.is_straight() { *part1_counts.entry(pair).or_insert(0) += 1 }
-            *counts.entry(pair).or_insert(0) += 1
+    fn parse(input: &str) -> Result<Vec<Segment>, Report> {
+        let lines = parse_all(input, lines(line_parser))?;
+        // Vents only ever line up along the grid or at 45 degrees, and other lines miss most grid points
+        if let Some((number, line)) = lines.iter().enumerate().find(|(_, l)| !l.is_axis_aligned() && !l.is_diagonal()) {
+            return Err(eyre!("Line {} isn't horizontal, vertical or at 45 degrees: {:?}", number + 1, line))
         }
+        Ok(lines)
     }
 
-    let part1 = part1_counts.values().filter(|&&c| c > 1).count();
-    info!(day=5, part=1, answer=part1);
-    let part2 = counts.values().filter(|&&c| c > 1).count();
-    info!(day=5, part=2, answer=part2);
+    fn part1(lines: &Vec<Segment>) -> Result<Answer, Report> {
+        Ok(overlaps(lines, false).into())
+    }
 
-    Ok(())
+    fn part2(lines: &Vec<Segment>) -> Result<Answer, Report> {
+        Ok(overlaps(lines, true).into())
+    }
+
+    fn generate(rng: &mut Rng, size: Option<usize>) -> String {
+        let mut text = String::new();
+        for _ in 0..size.unwrap_or(500) {
+            let start = Point2::new(rng.between(10, 989) as i32, rng.between(10, 989) as i32);
+            let direction = *rng.choose(&DIRECTIONS);
+            // As long as it can be without leaving the map
+            let room = |start: i32, step: i32| match step { 1 => 989 - start, -1 => start - 10, _ => i32::MAX };
+            let longest = room(start.x, direction.x).min(room(start.y, direction.y)).min(500);
+            let end = start + direction * rng.between(0, longest as i64) as i32;
+            text += &format!("{},{} -> {},{}\n", start.x, start.y, end.x, end.y);
+        }
+        text
+    }
 }
@@ -1,36 +1,108 @@
-use std::str::FromStr;
+use std::iter::repeat_n;
+use color_eyre::eyre::eyre;
 use color_eyre::Report;
-use tracing::{debug, info};
+use itertools::Itertools;
+use nom::character;
+use tracing::debug;
+use crate::animation::{bar_chart, Simulation};
+use crate::answer::Answer;
+use crate::parse::{comma_separated, parse_all};
+use crate::random::Rng;
+use crate::solver::Puzzle;
 
-const MAX_TIMER: usize = 10;
+/// A count for each timer, from 0 up to the 8 a newborn fish starts at
+pub const TIMERS: usize = 9;
 
-fn iterate(counts: &mut [u64; MAX_TIMER]) {
-    let add = counts[0];
-    for i in 0..9 {
-        counts[i] = counts[i+1];
+pub fn iterate(counts: &mut [u64; TIMERS]) {
+    // Fish at 0 go round to 8 as their newborns, and start again at 6 themselves
+    counts.rotate_left(1);
+    counts[6] += counts[8];
+}
+
+/// How many fish there are after `days`, following each one separately rather than counting them by timer
+pub fn naive_count(counts: &[u64; TIMERS], days: usize) -> usize {
+    let mut fish: Vec<u8> = counts.iter().enumerate()
+        .flat_map(|(timer, &count)| repeat_n(timer as u8, count as usize))
+        .collect();
+    for _ in 0..days {
+        let born = fish.iter().filter(|&&f| f == 0).count();
+        for f in fish.iter_mut() {
+            *f = if *f == 0 { 6 } else { *f - 1 };
+        }
+        fish.extend(repeat_n(8, born));
     }
-    counts[6] += add;
-    counts[8] += add;
+    fish.len()
+}
+
+/// A school of lanternfish, counted by timer, for animating
+pub struct School {
+    counts: [u64; TIMERS],
+    day: usize,
 }
 
-pub(crate) fn solve(input: String) -> Result<(), Report> {
-    let fish = input.split(',').map(i32::from_str).collect::<Result<Vec<_>, _>>()?;
-    let mut counts: [u64; MAX_TIMER] = Default::default();
+impl Simulation for School {
+    fn step(&mut self) {
+        iterate(&mut self.counts);
+        self.day += 1;
+    }
 
-    for f in fish {
-        counts[f as usize] += 1
+    fn frame(&self) -> String {
+        let bars: Vec<_> = self.counts.iter().enumerate().map(|(timer, &count)| (timer.to_string(), count)).collect();
+        format!("Day {}: {} fish\n\n{}", self.day, self.counts.iter().sum::<u64>(), bar_chart(&bars, 60))
     }
 
-    debug!("Initial counts: {:?}", counts);
-    for _ in 0..18 { iterate(&mut counts) }
-    debug!("After 18 days: {} fish ({:?})", counts.iter().sum::<u64>(), counts);
-    for _ in 18..80 { iterate(&mut counts) }
+    fn is_finished(&self) -> bool { self.day >= 256 }
+}
 
-    info!(day=6, part=1, answer=counts.iter().sum::<u64>());
+pub struct Day6;
 
-    for _ in 80..256 { iterate(&mut counts) }
+impl Puzzle for Day6 {
+    type Input = [u64; TIMERS];
+
+    const DAY: u32 = 6;
+    const TITLE: &'static str = "Lanternfish";
+
+    fn parse(input: &str) -> Result<[u64; TIMERS], Report> {
+        let fish = parse_all(input, comma_separated(character::complete::u8))?;
+        let mut counts: [u64; TIMERS] = Default::default();
+
+        for f in fish {
+            *counts.get_mut(f as usize).ok_or_else(|| eyre!("Timer {} is too big", f))? += 1
+        }
+
+        debug!("Initial counts: {:?}", counts);
+        Ok(counts)
+    }
 
-    info!(day=6, part=2, answer=counts.iter().sum::<u64>());
+    fn part1(counts: &[u64; TIMERS]) -> Result<Answer, Report> {
+        let mut counts = *counts;
+        for _ in 0..18 { iterate(&mut counts) }
+        debug!("After 18 days: {} fish ({:?})", counts.iter().sum::<u64>(), counts);
+        for _ in 18..80 { iterate(&mut counts) }
 
-    Ok(())
+        Ok(counts.iter().sum::<u64>().into())
+    }
+
+    fn part2(counts: &[u64; TIMERS]) -> Result<Answer, Report> {
+        let mut counts = *counts;
+        for _ in 0..256 { iterate(&mut counts) }
+
+        Ok(counts.iter().sum::<u64>().into())
+    }
+
+    fn simulation(counts: &[u64; TIMERS]) -> Result<Option<Box<dyn Simulation>>, Report> {
+        Ok(Some(Box::new(School { counts: *counts, day: 0 })))
+    }
+
+    fn reference(counts: &[u64; TIMERS], part: u32) -> Result<Option<Answer>, Report> {
+        // The school doubles about once a week, so there'd be far too many fish to follow after 256 days
+        Ok(match part {
+            1 => Some(naive_count(counts, 80).into()),
+            _ => None,
+        })
+    }
+
+    fn generate(rng: &mut Rng, size: Option<usize>) -> String {
+        (0..size.unwrap_or(300)).map(|_| rng.between(1, 5)).join(",") + "\n"
+    }
 }
@@ -1,12 +1,18 @@
 use std::collections::HashMap;
-use std::str::FromStr;
 use color_eyre::Report;
-use tracing::{debug, info};
+use itertools::Itertools;
+use nom::character;
+use tracing::debug;
+use crate::answer::Answer;
+use crate::parse::{comma_separated, parse_all};
+use crate::random::Rng;
+use crate::solver::Puzzle;
 
 const MAX_OFFSET: i32 = 200;
 
 
-fn part2_cost_to_move(distance: i32) -> i32 {
+pub fn part2_cost_to_move(distance: i32) -> i64 {
+    let distance = distance as i64;
     if distance % 2 == 0 {
         (distance + 1) * (distance / 2)
     } else {
@@ -14,32 +20,83 @@ fn part2_cost_to_move(distance: i32) -> i32 {
     }
 }
 
-pub(crate) fn solve(input: String) -> Result<(), Report> {
-    let positions = input.split(',').map(i32::from_str).collect::<Result<Vec<_>, _>>()?;
-    let average = positions.iter().sum::<i32>() / positions.len() as i32;
-    debug!("average position is {}", average);
+pub fn average(positions: &[i32]) -> i32 {
+    (positions.iter().map(|&p| p as i64).sum::<i64>() / positions.len() as i64) as i32
+}
+
+pub fn median(positions: &[i32]) -> i32 {
+    let mut sorted = positions.to_vec();
+    sorted.sort_unstable();
+    sorted[sorted.len() / 2]
+}
+
+/// Finds the best position near `around` and its total cost, given the cost to move a crab a distance
+pub fn cheapest_position(positions: &[i32], around: i32, cost_to_move: impl Fn(i32) -> i64) -> (i32, i64) {
+    debug!("looking around position {}", around);
 
     let mut scores = HashMap::new();
 
-    for position in (average - MAX_OFFSET).max(0)..(average + MAX_OFFSET) {
-        let total_distance = positions.iter().map(|pos| (position - pos).abs()).sum::<i32>();
+    for position in (around - MAX_OFFSET).max(0)..(around + MAX_OFFSET) {
+        let total_distance = positions.iter().map(|pos| cost_to_move((position - pos).abs())).sum::<i64>();
         scores.insert(position, total_distance);
     }
 
-    let best = scores.iter().min_by_key(|(_position, score)| **score).unwrap();
+    let (&position, &score) = scores.iter().min_by_key(|(_position, score)| **score).unwrap();
+    (position, score)
+}
+
+/// The cost of the cheapest position, found by trying every one from the leftmost crab to the rightmost
+pub fn naive_cheapest(positions: &[i32], cost_to_move: impl Fn(i32) -> i64) -> i64 {
+    let (&leftmost, &rightmost) = (positions.iter().min().unwrap_or(&0), positions.iter().max().unwrap_or(&0));
+    (leftmost..=rightmost)
+        .map(|position| positions.iter().map(|pos| cost_to_move((position - pos).abs())).sum())
+        .min()
+        .unwrap_or(0)
+}
 
-    info!(day=7, part=1, position=best.0, answer=best.1);
+pub struct Day7;
 
-    scores.clear();
+impl Puzzle for Day7 {
+    type Input = Vec<i32>;
 
-    for position in (average - MAX_OFFSET).max(0)..(average + MAX_OFFSET) {
-        let total_distance = positions.iter().map(|pos| part2_cost_to_move((position - pos).abs())).sum::<i32>();
-        scores.insert(position, total_distance);
+    const DAY: u32 = 7;
+    const TITLE: &'static str = "The Treachery of Whales";
+
+    fn parse(input: &str) -> Result<Vec<i32>, Report> {
+        // Positions are never negative, and keeping them small means the costs can't overflow
+        let positions = parse_all(input, comma_separated(character::complete::u16))?;
+        Ok(positions.into_iter().map(i32::from).collect())
     }
 
-    let best = scores.iter().min_by_key(|(_position, score)| **score).unwrap();
+    fn part1(positions: &Vec<i32>) -> Result<Answer, Report> {
+        // When every step costs the same, moving half the crabs one way costs the same as moving the other
+        // half back, so the median is cheapest, even when a few stragglers pull the average a long way off
+        let (position, answer) = cheapest_position(positions, median(positions), |distance| distance as i64);
 
-    info!(day=7, part=2, position=best.0, answer=best.1);
+        debug!(position=position);
 
-    Ok(())
-}
\ No newline at end of file
+        Ok(answer.into())
+    }
+
+    fn part2(positions: &Vec<i32>) -> Result<Answer, Report> {
+        // Costs that grow with the square of the distance are cheapest near the average
+        let (position, answer) = cheapest_position(positions, average(positions), part2_cost_to_move);
+
+        debug!(position=position);
+
+        Ok(answer.into())
+    }
+
+    fn reference(positions: &Vec<i32>, part: u32) -> Result<Option<Answer>, Report> {
+        Ok(match part {
+            1 => Some(naive_cheapest(positions, |distance| distance as i64).into()),
+            // Moving n steps costs 1 + 2 + ... + n
+            _ => Some(naive_cheapest(positions, |distance| (1..=distance as i64).sum()).into()),
+        })
+    }
+
+    fn generate(rng: &mut Rng, size: Option<usize>) -> String {
+        // Crabs bunch up towards the start, with a few stragglers a long way out
+        (0..size.unwrap_or(1000)).map(|_| { let spread = rng.below(2000) + 1; rng.below(spread) }).join(",") + "\n"
+    }
+}
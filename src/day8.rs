@@ -2,62 +2,50 @@ use color_eyre::eyre::eyre;
 use color_eyre::Report;
 use itertools::Itertools;
 use nom::bytes::complete::tag;
-use nom::character::complete::{alpha1, newline, space0};
-use nom::combinator::{all_consuming, opt};
-use nom::IResult;
-use nom::multi::{count, many1};
-use nom::sequence::{terminated, tuple};
-use tracing::{debug, info};
+use nom::character::complete::{alpha1, space0};
+use nom::multi::count;
+use nom::sequence::tuple;
+use tracing::debug;
+use crate::answer::Answer;
+use crate::parse::{lines, parse_all};
+use crate::parse_error::ParseResult;
+use crate::random::Rng;
+use crate::solver::Puzzle;
 
 #[derive(Debug)]
-struct TestCase {
-    all_digits: Vec<String>,
-    display: Vec<String>
+pub struct TestCase {
+    pub all_digits: Vec<String>,
+    pub display: Vec<String>
 }
 
-// fn get_digits() -> Vec<&'static str> {
-//     vec![
-//         "abcefg",
-//         "cf",
-//         "acdeg",
-//         "acdfg",
-//         "bcdf",
-//         "abdfg",
-//         "abdefg",
-//         "acf",
-//         "abcdefg",
-//         "abdcfg"
-//     ]
-// }
-//
-fn digit_parser(i: &str) -> IResult<&str, String> {
+/// The segments lit up for each digit, when the wires are connected the right way round
+const SEGMENTS: [&str; 10] = ["abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg"];
+
+fn digit_parser(i: &str) -> ParseResult<'_, String> {
     alpha1(i)
         .map(|(left, segments)| (left, segments.chars().sorted().collect()))
 }
 
-fn line_parser(i: &str) -> IResult<&str, TestCase> {
-    terminated(
-        tuple((
-            count(
-                tuple((
-                    space0,
-                    digit_parser,
-                    space0
-                )),
-                10),
-
-            tag("|"),
-
-            count(
-                tuple((
-                    space0,
-                    digit_parser,
-                    space0,
-                )),
-                4),
-        )),
-        opt(newline),
-    )(i)
+fn line_parser(i: &str) -> ParseResult<'_, TestCase> {
+    tuple((
+        count(
+            tuple((
+                space0,
+                digit_parser,
+                space0
+            )),
+            10),
+
+        tag("|"),
+
+        count(
+            tuple((
+                space0,
+                digit_parser,
+                space0,
+            )),
+            4),
+    ))(i)
         .map(|(left, (all_digits, _, display))|
             (left, TestCase {
                 all_digits: all_digits.into_iter().map(|(_, digit, _)| digit).collect(),
@@ -65,66 +53,95 @@ fn line_parser(i: &str) -> IResult<&str, TestCase> {
             }))
 }
 
-pub(crate) fn solve(input: String) -> Result<(), Report> {
-    let cases = match all_consuming(many1(line_parser))(&input) {
-        Ok(("", cases)) => cases,
-        Ok((leftovers, _)) => return Err(Report::msg(format!("Didn't parse all lines: {:?} left", leftovers))),
-        Err(e) => return Err(Report::msg(format!("Failed to parse input: {:?}", e)))
-    };
-
-    let output_digits_with_unique_number_of_segments = cases.iter().map(|m| m.display.iter().filter(|d| matches!(d.len(), 2 | 3 | 4 | 7)).count()).sum::<usize>();
-    info!(day=8, part=1, answer=output_digits_with_unique_number_of_segments);
-
-    let mut sum = 0;
-
-    for case in cases {
-        debug!("{:?}", case.all_digits);
-        let mut v = [""; 10];
-
-        // The only 2-segment digit is 1
-        v[1] = case.all_digits.iter().find(|&n| n.len() == 2).ok_or_else(|| eyre!("1 not found"))?;
-        debug!("1 = {}", v[1]);
-        // The only 4-segment digit is 4
-        v[4] = case.all_digits.iter().find(|&n| n.len() == 4).ok_or_else(|| eyre!("4 not found"))?;
-        debug!("4 = {}", v[4]);
-        // The only 3-segment digit is 7
-        v[7] = case.all_digits.iter().find(|&n| n.len() == 3).ok_or_else(|| eyre!("7 not found"))?;
-        debug!("7 = {}", v[7]);
-        // The only 7-segment digit is 8
-        v[8] = case.all_digits.iter().find(|&n| n.len() == 7).ok_or_else(|| eyre!("8 not found"))?;
-        debug!("8 = {}", v[8]);
-
-        // The only 6-segment digit that contains only one segment of 1 is 6
-        v[6] = case.all_digits.iter().find(|&n| n.len() == 6 && n.chars().filter(|&c| v[1].contains(c)).count() == 1).ok_or_else(|| eyre!("6 not found"))?;
-        debug!("6 = {}", v[6]);
-        // The only 6-segment digit that contains all 4 segments of 4 is 9
-        v[9] = case.all_digits.iter().find(|&n| n.len() == 6 && n.chars().filter(|&c| v[4].contains(c)).count() == 4).ok_or_else(|| eyre!("9 not found"))?;
-        debug!("9 = {}", v[9]);
-        // The remaining 6-segment digit is 0
-        v[0] = case.all_digits.iter().find(|&n| n.len() == 6 && n != v[6] && n != v[9]).ok_or_else(|| eyre!("0 not found"))?;
-        debug!("0 = {}", v[0]);
-
-        // The only 5-segment digit that contains both segments of 1 is 3
-        v[3] = case.all_digits.iter().find(|&n| n.len() == 5 && n.chars().filter(|&c| v[1].contains(c)).count() == 2).ok_or_else(|| eyre!("3 not found"))?;
-        debug!("3 = {}", v[3]);
-        // The only 5-segment digit that is entirely contained within the segments of 6 is 5
-        v[5] = case.all_digits.iter().find(|&n| n.len() == 5 && n.chars().filter(|&c| v[6].contains(c)).count() == 5).ok_or_else(|| eyre!("5 not found"))?;
-        debug!("5 = {}", v[5]);
-        // The remaining 5-segment digit is 2
-        v[2] = case.all_digits.iter().find(|&n| n.len() == 5 && n != v[3] && n != v[5]).ok_or_else(|| eyre!("2 not found"))?;
-        debug!("2 = {}", v[2]);
-
-        let mut result = 0;
-        for digit in case.display {
-            result *= 10;
-            result += v.iter().position(|&n| n == digit).ok_or_else(|| eyre!("Number for {} not found!", digit))?;
-        }
+pub struct Day8;
+
+impl Puzzle for Day8 {
+    type Input = Vec<TestCase>;
+
+    const DAY: u32 = 8;
+    const TITLE: &'static str = "Seven Segment Search";
+
+    fn parse(input: &str) -> Result<Vec<TestCase>, Report> {
+        Ok(parse_all(input, lines(line_parser))?)
+    }
 
-        debug!("result: {:?}", result);
-        sum += result;
+    fn part1(cases: &Vec<TestCase>) -> Result<Answer, Report> {
+        let output_digits_with_unique_number_of_segments = cases.iter().map(|m| m.display.iter().filter(|d| matches!(d.len(), 2 | 3 | 4 | 7)).count()).sum::<usize>();
+
+        Ok(output_digits_with_unique_number_of_segments.into())
     }
 
-    info!(day=8, part=2, answer=sum);
+    fn part2(cases: &Vec<TestCase>) -> Result<Answer, Report> {
+        let mut sum = 0;
+
+        for case in cases {
+            debug!("{:?}", case.all_digits);
+            let mut v = [""; 10];
+
+            // The only 2-segment digit is 1
+            v[1] = case.all_digits.iter().find(|&n| n.len() == 2).ok_or_else(|| eyre!("1 not found"))?;
+            debug!("1 = {}", v[1]);
+            // The only 4-segment digit is 4
+            v[4] = case.all_digits.iter().find(|&n| n.len() == 4).ok_or_else(|| eyre!("4 not found"))?;
+            debug!("4 = {}", v[4]);
+            // The only 3-segment digit is 7
+            v[7] = case.all_digits.iter().find(|&n| n.len() == 3).ok_or_else(|| eyre!("7 not found"))?;
+            debug!("7 = {}", v[7]);
+            // The only 7-segment digit is 8
+            v[8] = case.all_digits.iter().find(|&n| n.len() == 7).ok_or_else(|| eyre!("8 not found"))?;
+            debug!("8 = {}", v[8]);
+
+            // The only 6-segment digit that contains only one segment of 1 is 6
+            v[6] = case.all_digits.iter().find(|&n| n.len() == 6 && n.chars().filter(|&c| v[1].contains(c)).count() == 1).ok_or_else(|| eyre!("6 not found"))?;
+            debug!("6 = {}", v[6]);
+            // The only 6-segment digit that contains all 4 segments of 4 is 9
+            v[9] = case.all_digits.iter().find(|&n| n.len() == 6 && n.chars().filter(|&c| v[4].contains(c)).count() == 4).ok_or_else(|| eyre!("9 not found"))?;
+            debug!("9 = {}", v[9]);
+            // The remaining 6-segment digit is 0
+            v[0] = case.all_digits.iter().find(|&n| n.len() == 6 && n != v[6] && n != v[9]).ok_or_else(|| eyre!("0 not found"))?;
+            debug!("0 = {}", v[0]);
+
+            // The only 5-segment digit that contains both segments of 1 is 3
+            v[3] = case.all_digits.iter().find(|&n| n.len() == 5 && n.chars().filter(|&c| v[1].contains(c)).count() == 2).ok_or_else(|| eyre!("3 not found"))?;
+            debug!("3 = {}", v[3]);
+            // The only 5-segment digit that is entirely contained within the segments of 6 is 5
+            v[5] = case.all_digits.iter().find(|&n| n.len() == 5 && n.chars().filter(|&c| v[6].contains(c)).count() == 5).ok_or_else(|| eyre!("5 not found"))?;
+            debug!("5 = {}", v[5]);
+            // The remaining 5-segment digit is 2
+            v[2] = case.all_digits.iter().find(|&n| n.len() == 5 && n != v[3] && n != v[5]).ok_or_else(|| eyre!("2 not found"))?;
+            debug!("2 = {}", v[2]);
+
+            let mut result = 0;
+            for digit in &case.display {
+                result *= 10;
+                result += v.iter().position(|&n| n == *digit).ok_or_else(|| eyre!("Number for {} not found!", digit))?;
+            }
+
+            debug!("result: {:?}", result);
+            sum += result;
+        }
+
+        Ok(sum.into())
+    }
 
-    Ok(())
+    fn generate(rng: &mut Rng, size: Option<usize>) -> String {
+        let mut text = String::new();
+        for _ in 0..size.unwrap_or(200) {
+            let mut digits: Vec<usize> = (0..10).collect();
+            rng.shuffle(&mut digits);
+            let display: Vec<usize> = (0..4).map(|_| rng.below(10)).collect();
+
+            // Each display is wired up differently
+            let mut wires: Vec<char> = "abcdefg".chars().collect();
+            rng.shuffle(&mut wires);
+            let mut wired = |digit: &usize| {
+                let mut segments: Vec<char> = SEGMENTS[*digit].bytes().map(|s| wires[(s - b'a') as usize]).collect();
+                rng.shuffle(&mut segments);
+                segments.into_iter().collect::<String>()
+            };
+            let all_digits = digits.iter().map(&mut wired).join(" ");
+            text += &format!("{} | {}\n", all_digits, display.iter().map(&mut wired).join(" "));
+        }
+        text
+    }
 }
@@ -1,78 +1,105 @@
 use std::collections::HashMap;
 use color_eyre::Report;
 use itertools::Itertools;
-use tracing::{debug, info};
+use tracing::debug;
+use crate::answer::Answer;
+use crate::grid::{Grid, Position};
+use crate::parse::{digit_grid, parse_all};
+use crate::picture::{Color, ColorMap, Picture};
+use crate::random::{self, Rng};
+use crate::solver::Puzzle;
 
 const WALL: u8 = 9;
 
-fn fill(v: &mut Vec<Vec<u8>>, grid: &[Vec<u8>], row: usize, col: usize, fill_with: u8) {
-    if grid[row][col] == WALL { return }
-    if v[row][col] != 0 { return }
-    v[row][col] = fill_with;
-    if row > 0 { fill(v, grid, row - 1, col, fill_with) }
-    if row < v.len() - 1 { fill(v, grid, row + 1, col, fill_with) }
-    if col > 0 { fill(v, grid, row, col - 1, fill_with) }
-    if col < v[0].len() - 1 { fill(v, grid, row, col + 1, fill_with) }
+/// Marks every cell in the basin containing `start` with `fill_with`
+fn fill(basins: &mut Grid<u32>, grid: &Grid<u8>, start: Position, fill_with: u32) {
+    let mut to_fill = vec![start];
+    while let Some(position) = to_fill.pop() {
+        if grid[position] == WALL || basins[position] != 0 { continue }
+        basins[position] = fill_with;
+        to_fill.extend(grid.neighbors4(position));
+    }
 }
 
-pub(crate) fn solve(input: String) -> Result<(), Report> {
-    let lines: Vec<_> = input.trim().split('\n').collect();
-    let mut grid = Vec::with_capacity(lines.len());
-    for line in lines {
-        let row: Vec<_> = line.bytes().map(|b| b - b'0').collect();
-        grid.push(row)
+/// Numbers every cell by the basin it's in, starting from 1, with 0 for the walls between them
+pub fn basins(grid: &Grid<u8>) -> Grid<u32> {
+    let mut basins = Grid::new(grid.width(), grid.height(), 0u32);
+    let mut current_basin = 1;
+
+    for position in grid.positions() {
+        if grid[position] == WALL { continue }
+        if basins[position] == 0 {
+            fill(&mut basins, grid, position, current_basin);
+            current_basin += 1;
+        }
     }
 
-    debug!("{:?}", grid);
+    basins
+}
 
-    let rows = grid.len();
-    let cols = grid[0].len();
+pub struct Day9;
 
-    let mut part1_sum = 0;
+impl Puzzle for Day9 {
+    type Input = Grid<u8>;
 
-    for row in 0..rows {
-        for col in 0..cols {
-            if (col == cols - 1 || grid[row][col+1] > grid[row][col]) &&
-                (col == 0 || grid[row][col-1] > grid[row][col]) &&
-                (row == rows - 1 || grid[row+1][col] > grid[row][col]) &&
-                (row == 0 || grid[row-1][col] > grid[row][col]) {
-                debug!("low point at {}, {}: {}", row, col, grid[row][col]);
-                part1_sum += grid[row][col] as u32 + 1;
-            }
-        }
-    }
+    const DAY: u32 = 9;
+    const TITLE: &'static str = "Smoke Basin";
+
+    fn parse(input: &str) -> Result<Grid<u8>, Report> {
+        let grid = parse_all(input, digit_grid)?;
 
-    info!(day=9, part=1, answer=part1_sum);
+        debug!("\n{}", grid);
+
+        Ok(grid)
+    }
 
-    let mut partitions = vec![vec![0u8; cols]; rows];
-    let mut current_partition = 1;
+    fn part1(grid: &Grid<u8>) -> Result<Answer, Report> {
+        let mut part1_sum = 0;
 
-    for row in 0..rows {
-        for col in 0..cols {
-            if grid[row][col] == WALL { continue }
-            if partitions[row][col] == 0 {
-                fill(&mut partitions, &grid, row, col, current_partition);
-                current_partition += 1;
+        for (position, &height) in grid.cells() {
+            if grid.neighbors4(position).all(|n| grid[n] > height) {
+                debug!("low point at {:?}: {}", position, height);
+                part1_sum += height as u32 + 1;
             }
         }
+
+        Ok(part1_sum.into())
     }
 
-    let mut sizes = HashMap::new();
-    for row in &partitions {
-        for &col in row {
-            if col != 0 {
-                *sizes.entry(col).or_insert(0) += 1;
+    fn part2(grid: &Grid<u8>) -> Result<Answer, Report> {
+        let basins = basins(grid);
+
+        let mut sizes = HashMap::new();
+        for &basin in basins.iter() {
+            if basin != 0 {
+                *sizes.entry(basin).or_insert(0) += 1;
             }
         }
-    }
 
-    let top_3: Vec<_> = sizes.iter().sorted_by_key(|(_, &v)| -v).take(3).collect();
-    debug!("{:?}", sizes);
-    debug!("{:?}", top_3);
-    // debug!("{:?}", partitions);
+        let top_3: Vec<_> = sizes.iter().sorted_by_key(|(_, &v)| -v).take(3).collect();
+        debug!("{:?}", sizes);
+        debug!("{:?}", top_3);
 
-    let answer: i32 = top_3.iter().map(|(_, &v)| v).product();
-    info!(day=9, part=2, answer=answer);
+        let answer: i32 = top_3.iter().map(|(_, &v)| v).product();
+
+        Ok(answer.into())
+    }
 
-    Ok(())
+    fn picture(grid: &Grid<u8>, colors: Option<ColorMap>) -> Result<Option<Picture>, Report> {
+        let colors = colors.unwrap_or(ColorMap::Labels);
+        if colors != ColorMap::Labels {
+            return Ok(Some(Picture::new(grid, |_, &height| colors.color(height as u32, 9))))
+        }
+        // Each basin in its own colour, lighter towards its rim
+        let basins = basins(grid);
+        Ok(Some(Picture::new(grid, |position, &height| match basins[position] {
+            0 => Color::BLACK,
+            basin => Color::label(basin as usize).blend(Color::WHITE, height as f64 / 16.0),
+        })))
+    }
+
+    fn generate(rng: &mut Rng, size: Option<usize>) -> String {
+        let side = size.unwrap_or(100);
+        random::grid(rng, side, side, |rng| char::from(b'0' + rng.below(10) as u8))
+    }
 }
@@ -0,0 +1,97 @@
+//! Runs every example input in `data/` against the answers stored next to it.
+//!
+//! An example is any `dayN-NAME.txt` file; its expected answers live in `dayN-NAME.answers.toml`,
+//! with `part1`/`part2` keys (either may be left out) or a `skip` key giving the reason not to run it.
+
+use std::fs::{read_dir, read_to_string};
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::eyre;
+use color_eyre::Report;
+use serde::Deserialize;
+
+use crate::runner::run_day;
+use crate::solver;
+use crate::verify::{check, Expected, ExpectedAnswers};
+
+#[derive(Debug, Deserialize)]
+struct ExampleAnswers {
+    part1: Option<Expected>,
+    part2: Option<Expected>,
+    skip: Option<String>,
+}
+
+#[derive(Debug)]
+struct Example {
+    day: u32,
+    input: PathBuf,
+    answers: PathBuf,
+}
+
+fn data_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("data")
+}
+
+/// Finds the example inputs in `dir`, leaving out the full puzzle inputs (`dayN.txt`)
+fn find_examples(dir: &Path) -> Result<Vec<Example>, Report> {
+    let mut examples = vec![];
+    for entry in read_dir(dir)? {
+        let path = entry?.path();
+        let name = match path.file_name().and_then(|n| n.to_str()) {
+            Some(name) => name,
+            None => continue,
+        };
+        let stem = match name.strip_prefix("day").and_then(|n| n.strip_suffix(".txt")) {
+            Some(stem) => stem,
+            None => continue,
+        };
+        let (day, _) = match stem.split_once('-') {
+            Some(parts) => parts,
+            None => continue,
+        };
+        let day = day.parse().map_err(|_| eyre!("Can't tell which day {} is for", name))?;
+        let answers = path.with_file_name(format!("day{}.answers.toml", stem));
+        examples.push(Example { day, input: path, answers });
+    }
+    examples.sort_by(|a, b| (a.day, &a.input).cmp(&(b.day, &b.input)));
+    Ok(examples)
+}
+
+/// Runs one example, returning a description of each way it went wrong
+fn run_example(example: &Example) -> Result<Vec<String>, Report> {
+    let text = read_to_string(&example.answers)
+        .map_err(|e| Report::new(e).wrap_err(format!("No expected answers for {}", example.input.display())))?;
+    let answers: ExampleAnswers = toml::from_str(&text)?;
+    if answers.skip.is_some() {
+        return Ok(vec![])
+    }
+
+    let solver = solver::get(example.day)?;
+    let run = run_day(solver, &example.input);
+    let expected = ExpectedAnswers { day: example.day, input: example.input.clone(), part1: answers.part1, part2: answers.part2 };
+    Ok(check(&run, &expected).iter().map(|m| m.to_string()).collect())
+}
+
+#[test]
+fn examples_match_expected_answers() {
+    let examples = find_examples(&data_dir()).unwrap();
+    assert!(!examples.is_empty(), "no examples found in {}", data_dir().display());
+
+    let mut failures = vec![];
+    for example in &examples {
+        match run_example(example) {
+            Ok(mismatches) => failures.extend(mismatches),
+            Err(e) => failures.push(format!("{}: {:#}", example.input.display(), e)),
+        }
+    }
+
+    assert!(failures.is_empty(), "{} of {} examples failed:\n{}", failures.len(), examples.len(), failures.join("\n"));
+}
+
+#[test]
+fn every_registered_day_has_an_example() {
+    let examples = find_examples(&data_dir()).unwrap();
+    for solver in solver::SOLVERS {
+        assert!(examples.iter().any(|e| e.day == solver.day()), "day {} has no example input", solver.day());
+    }
+}
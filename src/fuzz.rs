@@ -0,0 +1,272 @@
+//! Feeds each day's parser and solver mangled versions of its examples, looking for panics and hangs.
+//!
+//! Anything that panics is shrunk to the smallest input that still panics in the same place and saved to
+//! the corpus, so it can be replayed as a test once it's fixed. Inputs the parser rejects, or that the
+//! solver returns an error for, are fine: that's what errors are for.
+
+use std::cell::RefCell;
+use std::fmt::{Display, Formatter};
+use std::fs::{create_dir_all, read_to_string, write};
+use std::panic::{catch_unwind, set_hook, take_hook, AssertUnwindSafe, PanicHookInfo};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use color_eyre::Report;
+use tracing::debug;
+
+use crate::random::Rng;
+use crate::runner::{day_files, panic_message};
+use crate::solver::Solver;
+
+/// What happened when a day was given one input
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Outcome {
+    /// Solved, or turned down with an error
+    Handled,
+    Panicked { location: String, message: String },
+    TimedOut,
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Handled => write!(f, "handled"),
+            Outcome::Panicked { location, message } => write!(f, "panicked at {}: {}", location, message),
+            Outcome::TimedOut => write!(f, "timed out"),
+        }
+    }
+}
+
+thread_local! {
+    /// Where the last panic on this thread happened, recorded by the hook `quiet_panics` installs
+    static PANIC_LOCATION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+type Hook = Box<dyn Fn(&PanicHookInfo<'_>) + Sync + Send + 'static>;
+
+/// Stops panics being printed while it's alive, remembering where they happened instead
+pub struct QuietPanics(Option<Hook>);
+
+pub fn quiet_panics() -> QuietPanics {
+    let previous = take_hook();
+    set_hook(Box::new(|info| {
+        let location = info.location().map(|l| format!("{}:{}", l.file(), l.line()));
+        PANIC_LOCATION.with(|l| *l.borrow_mut() = location);
+    }));
+    QuietPanics(Some(previous))
+}
+
+impl Drop for QuietPanics {
+    fn drop(&mut self) {
+        if let Some(previous) = self.0.take() {
+            set_hook(previous);
+        }
+    }
+}
+
+/// Parses `input` and solves both parts on another thread, giving up on it after `timeout`. A thread that
+/// times out is left running, since there's no stopping it.
+pub fn run_case(solver: &'static dyn Solver, input: &str, timeout: Duration) -> Outcome {
+    let (sender, receiver) = mpsc::channel();
+    let input = input.to_owned();
+    thread::spawn(move || {
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            if let Ok(parsed) = solver.parse(&input) {
+                let _ = solver.part1(&parsed);
+                let _ = solver.part2(&parsed);
+            }
+        }));
+        let outcome = match result {
+            Ok(()) => Outcome::Handled,
+            Err(payload) => Outcome::Panicked {
+                location: PANIC_LOCATION.with(|l| l.borrow_mut().take()).unwrap_or_else(|| "an unknown location".to_owned()),
+                message: panic_message(payload.as_ref()),
+            },
+        };
+        let _ = sender.send(outcome);
+    });
+    receiver.recv_timeout(timeout).unwrap_or(Outcome::TimedOut)
+}
+
+const NUMBERS: [&str; 8] = ["0", "1", "-1", "255", "65536", "2147483648", "99999999999999999999", "-9223372036854775808"];
+
+/// Changes `input` in a few random ways: swapping, adding or removing characters, lines and numbers
+pub fn mutate(input: &str, rng: &mut Rng) -> String {
+    let mut alphabet: Vec<char> = input.chars().collect();
+    alphabet.extend(['\n', ' ', ',', '-', '0', '9', 'é']);
+    let mut chars: Vec<char> = input.chars().collect();
+
+    for _ in 0..rng.between(1, 4) {
+        let len = chars.len();
+        match rng.below(8) {
+            0 if len > 0 => {
+                let at = rng.below(len);
+                chars[at] = *rng.choose(&alphabet);
+            }
+            1 => chars.insert(rng.below(len + 1), *rng.choose(&alphabet)),
+            2 if len > 0 => {
+                let start = rng.below(len);
+                let end = (start + rng.between(1, 8) as usize).min(len);
+                chars.drain(start..end);
+            }
+            3 if len > 0 => {
+                let start = rng.below(len);
+                let end = (start + rng.between(1, 16) as usize).min(len);
+                let copy: Vec<char> = chars[start..end].to_vec();
+                let at = rng.below(len + 1);
+                chars.splice(at..at, copy);
+            }
+            4 => chars.truncate(rng.below(len + 1)),
+            5 => {
+                // Replace a run of digits with an awkward number
+                let digits: Vec<usize> = (0..len).filter(|&i| chars[i].is_ascii_digit()).collect();
+                if !digits.is_empty() {
+                    let start = *rng.choose(&digits);
+                    let end = (start..len).find(|&i| !chars[i].is_ascii_digit()).unwrap_or(len);
+                    chars.splice(start..end, rng.choose(&NUMBERS).chars());
+                }
+            }
+            6 => {
+                let text: String = chars.iter().collect();
+                let mut lines: Vec<&str> = text.split('\n').collect();
+                let at = rng.below(lines.len());
+                match rng.below(3) {
+                    0 => { lines.remove(at); }
+                    1 => lines.insert(at, lines[at]),
+                    _ => { let other = rng.below(lines.len()); lines.swap(at, other); }
+                }
+                chars = lines.join("\n").chars().collect();
+            }
+            _ => chars.clear(),
+        }
+    }
+    chars.into_iter().collect()
+}
+
+/// Removes as much of `input` as it can while it still panics at `location`
+pub fn minimize(solver: &'static dyn Solver, input: &str, location: &str, timeout: Duration) -> String {
+    let still_panics = |candidate: &[char]| matches!(
+        run_case(solver, &candidate.iter().collect::<String>(), timeout),
+        Outcome::Panicked { location: l, .. } if l == location
+    );
+
+    let mut chars: Vec<char> = input.chars().collect();
+    let mut chunk = chars.len().div_ceil(2).max(1);
+    loop {
+        let mut start = 0;
+        while start < chars.len() {
+            let end = (start + chunk).min(chars.len());
+            let candidate: Vec<char> = chars[..start].iter().chain(&chars[end..]).copied().collect();
+            if still_panics(&candidate) {
+                chars = candidate;
+            } else {
+                start += chunk;
+            }
+        }
+        if chunk == 1 {
+            break
+        }
+        chunk /= 2;
+    }
+    chars.into_iter().collect()
+}
+
+/// Something that went wrong for a day
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub day: u32,
+    pub outcome: Outcome,
+    pub input: String,
+    pub saved: PathBuf,
+}
+
+/// Runs every crash saved in `corpus` through its day again, returning the ones that still don't work
+pub fn replay(corpus: &Path, timeout: Duration) -> Result<Vec<Finding>, Report> {
+    let _quiet = quiet_panics();
+    let mut findings = vec![];
+    for solver in crate::solver::SOLVERS {
+        for path in day_files(corpus, solver.day())? {
+            let input = read_to_string(&path)?;
+            let outcome = run_case(*solver, &input, timeout);
+            if outcome != Outcome::Handled {
+                findings.push(Finding { day: solver.day(), outcome, input, saved: path });
+            }
+        }
+    }
+    Ok(findings)
+}
+
+/// Names a saved input after where it panicked, so the same crash isn't saved twice
+fn corpus_name(day: u32, outcome: &Outcome) -> String {
+    match outcome {
+        Outcome::Panicked { location, .. } => {
+            let place: String = location.rsplit('/').next().unwrap_or(location)
+                .replace(".rs:", "-")
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+                .collect();
+            format!("day{}-{}.txt", day, place)
+        }
+        _ => format!("day{}-hang.txt", day),
+    }
+}
+
+/// Settings for one fuzzing run
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub runs: usize,
+    pub seed: u64,
+    pub timeout: Duration,
+    /// Where the example inputs to start from are
+    pub examples: PathBuf,
+    /// Where crashing inputs are saved, and read back as extra starting points
+    pub corpus: PathBuf,
+}
+
+/// Tries `options.runs` mutated inputs on `solver`, returning each distinct panic, and the first hang
+pub fn fuzz(solver: &'static dyn Solver, options: &Options) -> Result<Vec<Finding>, Report> {
+    let day = solver.day();
+    let mut seeds = vec![];
+    for file in day_files(&options.examples, day)?.iter().chain(&day_files(&options.corpus, day)?) {
+        seeds.push(read_to_string(file)?);
+    }
+    if seeds.is_empty() {
+        seeds.push(String::new());
+    }
+
+    let _quiet = quiet_panics();
+    let mut rng = Rng::new(options.seed ^ day as u64);
+    let mut findings: Vec<Finding> = vec![];
+    for run in 0..options.runs {
+        let seed = rng.below(seeds.len());
+        let input = mutate(&seeds[seed], &mut rng);
+        let outcome = match run_case(solver, &input, options.timeout) {
+            Outcome::Handled => continue,
+            outcome => outcome,
+        };
+        if findings.iter().any(|f| corpus_name(day, &f.outcome) == corpus_name(day, &outcome)) {
+            continue
+        }
+
+        debug!("day {} run {}: {}", day, run, outcome);
+        let input = match &outcome {
+            Outcome::Panicked { location, .. } => minimize(solver, &input, location, options.timeout),
+            _ => input,
+        };
+        // Hangs are kept apart so that replaying the corpus doesn't get stuck on them
+        let timed_out = outcome == Outcome::TimedOut;
+        let dir = if timed_out { options.corpus.join("hangs") } else { options.corpus.clone() };
+        let path = dir.join(corpus_name(day, &outcome));
+        create_dir_all(&dir)?;
+        write(&path, &input)?;
+
+        findings.push(Finding { day, outcome, input, saved: path });
+        // Whatever timed out is still running, which would throw off every timing after it
+        if timed_out {
+            break
+        }
+    }
+    Ok(findings)
+}
@@ -0,0 +1,255 @@
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+/// A point or offset on an integer plane
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct Point2 {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// A point or offset in integer space
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct Point3 {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl Point2 {
+    pub const ORIGIN: Point2 = Point2 { x: 0, y: 0 };
+
+    pub const fn new(x: i32, y: i32) -> Point2 {
+        Point2 { x, y }
+    }
+
+    pub fn manhattan_distance(self, other: Point2) -> i32 {
+        (other.x - self.x).abs() + (other.y - self.y).abs()
+    }
+
+    pub fn squared_distance(self, other: Point2) -> i32 {
+        (other.x - self.x).pow(2) + (other.y - self.y).pow(2)
+    }
+
+    /// Each coordinate replaced by its sign, giving a single step in the same direction
+    pub fn signum(self) -> Point2 {
+        Point2 { x: self.x.signum(), y: self.y.signum() }
+    }
+}
+
+impl Point3 {
+    pub const ORIGIN: Point3 = Point3 { x: 0, y: 0, z: 0 };
+
+    pub const fn new(x: i32, y: i32, z: i32) -> Point3 {
+        Point3 { x, y, z }
+    }
+
+    pub fn manhattan_distance(self, other: Point3) -> i32 {
+        (other.x - self.x).abs() + (other.y - self.y).abs() + (other.z - self.z).abs()
+    }
+
+    pub fn squared_distance(self, other: Point3) -> i32 {
+        (other.x - self.x).pow(2) + (other.y - self.y).pow(2) + (other.z - self.z).pow(2)
+    }
+
+    pub fn signum(self) -> Point3 {
+        Point3 { x: self.x.signum(), y: self.y.signum(), z: self.z.signum() }
+    }
+}
+
+macro_rules! vector_ops {
+    ($t:ident, $($c:ident),*) => {
+        impl Add for $t {
+            type Output = $t;
+            fn add(self, rhs: $t) -> $t { $t { $($c: self.$c + rhs.$c),* } }
+        }
+
+        impl Sub for $t {
+            type Output = $t;
+            fn sub(self, rhs: $t) -> $t { $t { $($c: self.$c - rhs.$c),* } }
+        }
+
+        impl Neg for $t {
+            type Output = $t;
+            fn neg(self) -> $t { $t { $($c: -self.$c),* } }
+        }
+
+        impl Mul<i32> for $t {
+            type Output = $t;
+            fn mul(self, rhs: i32) -> $t { $t { $($c: self.$c * rhs),* } }
+        }
+
+        impl AddAssign for $t {
+            fn add_assign(&mut self, rhs: $t) { $(self.$c += rhs.$c;)* }
+        }
+
+        impl SubAssign for $t {
+            fn sub_assign(&mut self, rhs: $t) { $(self.$c -= rhs.$c;)* }
+        }
+    }
+}
+
+vector_ops!(Point2, x, y);
+vector_ops!(Point3, x, y, z);
+
+/// An axis-aligned rectangle, including its edges
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Rect {
+    pub min: Point2,
+    pub max: Point2,
+}
+
+impl Rect {
+    /// The rectangle with `a` and `b` at opposite corners
+    pub fn from_corners(a: Point2, b: Point2) -> Rect {
+        Rect {
+            min: Point2::new(a.x.min(b.x), a.y.min(b.y)),
+            max: Point2::new(a.x.max(b.x), a.y.max(b.y)),
+        }
+    }
+
+    pub fn contains(&self, p: Point2) -> bool {
+        self.min.x <= p.x && p.x <= self.max.x && self.min.y <= p.y && p.y <= self.max.y
+    }
+
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let min = Point2::new(self.min.x.max(other.min.x), self.min.y.max(other.min.y));
+        let max = Point2::new(self.max.x.min(other.max.x), self.max.y.min(other.max.y));
+        if min.x <= max.x && min.y <= max.y { Some(Rect { min, max }) } else { None }
+    }
+
+    /// The number of integer points inside
+    pub fn area(&self) -> i64 {
+        (self.max.x - self.min.x + 1) as i64 * (self.max.y - self.min.y + 1) as i64
+    }
+}
+
+/// An axis-aligned box, including its faces
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Cuboid {
+    pub min: Point3,
+    pub max: Point3,
+}
+
+impl Cuboid {
+    pub fn from_corners(a: Point3, b: Point3) -> Cuboid {
+        Cuboid {
+            min: Point3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            max: Point3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+        }
+    }
+
+    pub fn contains(&self, p: Point3) -> bool {
+        self.min.x <= p.x && p.x <= self.max.x && self.min.y <= p.y && p.y <= self.max.y && self.min.z <= p.z && p.z <= self.max.z
+    }
+
+    pub fn intersection(&self, other: &Cuboid) -> Option<Cuboid> {
+        let min = Point3::new(self.min.x.max(other.min.x), self.min.y.max(other.min.y), self.min.z.max(other.min.z));
+        let max = Point3::new(self.max.x.min(other.max.x), self.max.y.min(other.max.y), self.max.z.min(other.max.z));
+        if min.x <= max.x && min.y <= max.y && min.z <= max.z { Some(Cuboid { min, max }) } else { None }
+    }
+
+    /// The number of integer points inside
+    pub fn volume(&self) -> i64 {
+        (self.max.x - self.min.x + 1) as i64 * (self.max.y - self.min.y + 1) as i64 * (self.max.z - self.min.z + 1) as i64
+    }
+}
+
+/// A line segment between two points, including both ends
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Segment {
+    pub start: Point2,
+    pub end: Point2,
+}
+
+impl Segment {
+    pub fn new(start: Point2, end: Point2) -> Segment {
+        Segment { start, end }
+    }
+
+    pub fn is_horizontal(&self) -> bool { self.start.y == self.end.y }
+    pub fn is_vertical(&self) -> bool { self.start.x == self.end.x }
+    pub fn is_axis_aligned(&self) -> bool { self.is_horizontal() || self.is_vertical() }
+
+    /// Whether the segment runs at exactly 45 degrees
+    pub fn is_diagonal(&self) -> bool {
+        let d = self.end - self.start;
+        d.x != 0 && d.x.abs() == d.y.abs()
+    }
+
+    /// Every integer point on a horizontal, vertical or diagonal segment, from start to end
+    pub fn points(&self) -> impl Iterator<Item = Point2> {
+        let step = (self.end - self.start).signum();
+        let steps = (self.end.x - self.start.x).abs().max((self.end.y - self.start.y).abs());
+        let start = self.start;
+        (0..=steps).map(move |i| start + step * i)
+    }
+}
+
+/// A rotation of integer space by a multiple of 90 degrees about each axis
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Rotation {
+    pub matrix: [[i32; 3]; 3],
+}
+
+impl Rotation {
+    pub const IDENTITY: Rotation = Rotation { matrix: [[1, 0, 0], [0, 1, 0], [0, 0, 1]] };
+
+    /// All 24 ways of turning a cube, starting with the identity
+    pub fn all() -> Vec<Rotation> {
+        // Every signed permutation matrix with determinant 1
+        let permutations = [[0, 1, 2], [0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1], [2, 1, 0]];
+        let mut rotations = vec![];
+        for permutation in &permutations {
+            for signs in 0..8 {
+                let mut matrix = [[0; 3]; 3];
+                for (row, &col) in permutation.iter().enumerate() {
+                    matrix[row][col] = if signs & (1 << row) == 0 { 1 } else { -1 };
+                }
+                let rotation = Rotation { matrix };
+                if rotation.determinant() == 1 {
+                    rotations.push(rotation);
+                }
+            }
+        }
+        rotations
+    }
+
+    pub fn determinant(&self) -> i32 {
+        let m = &self.matrix;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    pub fn apply(&self, p: Point3) -> Point3 {
+        let m = &self.matrix;
+        Point3 {
+            x: m[0][0] * p.x + m[0][1] * p.y + m[0][2] * p.z,
+            y: m[1][0] * p.x + m[1][1] * p.y + m[1][2] * p.z,
+            z: m[2][0] * p.x + m[2][1] * p.y + m[2][2] * p.z,
+        }
+    }
+
+    /// The rotation that applies `other` first and then this one
+    pub fn compose(&self, other: &Rotation) -> Rotation {
+        let mut matrix = [[0; 3]; 3];
+        for (row, cells) in matrix.iter_mut().enumerate() {
+            for (col, cell) in cells.iter_mut().enumerate() {
+                *cell = (0..3).map(|k| self.matrix[row][k] * other.matrix[k][col]).sum();
+            }
+        }
+        Rotation { matrix }
+    }
+
+    /// The rotation that undoes this one
+    pub fn inverse(&self) -> Rotation {
+        // Rotation matrices are orthogonal, so the inverse is the transpose
+        let mut matrix = [[0; 3]; 3];
+        for (row, cells) in matrix.iter_mut().enumerate() {
+            for (col, cell) in cells.iter_mut().enumerate() {
+                *cell = self.matrix[col][row];
+            }
+        }
+        Rotation { matrix }
+    }
+}
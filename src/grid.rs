@@ -0,0 +1,162 @@
+use std::fmt::{Display, Formatter};
+use std::ops::{Index, IndexMut};
+
+use color_eyre::eyre::eyre;
+use color_eyre::Report;
+
+/// A cell's (row, column)
+pub type Position = (usize, usize);
+
+/// Offsets to the cells above, left, right and below
+pub const ORTHOGONAL: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+
+/// Offsets to all eight surrounding cells, in reading order
+pub const SURROUNDING: [(isize, isize); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+
+/// A rectangular grid of cells, stored row by row
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Builds a grid from rows, which must all be the same length
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Grid<T>, Report> {
+        let height = rows.len();
+        let width = rows.first().map_or(0, |r| r.len());
+        if let Some((row, bad)) = rows.iter().enumerate().find(|(_, r)| r.len() != width) {
+            return Err(eyre!("Row {} has {} cells but row 0 has {}", row, bad.len(), width))
+        }
+        Ok(Grid { width, height, cells: rows.into_iter().flatten().collect() })
+    }
+
+    pub fn from_fn(width: usize, height: usize, mut f: impl FnMut(Position) -> T) -> Grid<T> {
+        let cells = (0..height).flat_map(|row| (0..width).map(move |col| (row, col))).map(&mut f).collect();
+        Grid { width, height, cells }
+    }
+
+    pub fn width(&self) -> usize { self.width }
+    pub fn height(&self) -> usize { self.height }
+
+    fn index_of(&self, (row, col): Position) -> Option<usize> {
+        if row < self.height && col < self.width { Some(row * self.width + col) } else { None }
+    }
+
+    pub fn get(&self, position: Position) -> Option<&T> {
+        self.index_of(position).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, position: Position) -> Option<&mut T> {
+        self.index_of(position).map(move |i| &mut self.cells[i])
+    }
+
+    /// Looks up a cell by signed coordinates, which may be off the grid in any direction
+    pub fn get_signed(&self, row: isize, col: isize) -> Option<&T> {
+        if row < 0 || col < 0 { return None }
+        self.get((row as usize, col as usize))
+    }
+
+    /// The position `(dr, dc)` away from `position`, if that's still on the grid
+    pub fn offset(&self, (row, col): Position, (dr, dc): (isize, isize)) -> Option<Position> {
+        let row = row.checked_add_signed(dr)?;
+        let col = col.checked_add_signed(dc)?;
+        if row < self.height && col < self.width { Some((row, col)) } else { None }
+    }
+
+    /// The cells above, left, right and below `position` that are on the grid
+    pub fn neighbors4(&self, position: Position) -> impl Iterator<Item = Position> + '_ {
+        ORTHOGONAL.iter().filter_map(move |&d| self.offset(position, d))
+    }
+
+    /// All eight surrounding cells that are on the grid
+    pub fn neighbors8(&self, position: Position) -> impl Iterator<Item = Position> + '_ {
+        SURROUNDING.iter().filter_map(move |&d| self.offset(position, d))
+    }
+
+    /// Every position on the grid, in reading order
+    pub fn positions(&self) -> impl Iterator<Item = Position> {
+        let width = self.width;
+        (0..self.height).flat_map(move |row| (0..width).map(move |col| (row, col)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.cells.iter_mut()
+    }
+
+    /// Every cell along with its position, in reading order
+    pub fn cells(&self) -> impl Iterator<Item = (Position, &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid { width: self.width, height: self.height, cells: self.cells.iter().map(f).collect() }
+    }
+
+    /// Repeats the grid `across` times to the right and `down` times downwards; `f` gets each original cell
+    /// along with the (row, column) of the copy it's going into
+    pub fn tile(&self, across: usize, down: usize, mut f: impl FnMut(&T, Position) -> T) -> Grid<T> {
+        Grid::from_fn(self.width * across, self.height * down, |(row, col)| {
+            let original = &self[(row % self.height, col % self.width)];
+            f(original, (row / self.height, col / self.width))
+        })
+    }
+
+    /// Draws the grid one character per cell
+    pub fn render(&self, mut f: impl FnMut(&T) -> char) -> String {
+        let mut s = String::with_capacity((self.width + 1) * self.height);
+        for row in self.rows() {
+            s.extend(row.iter().map(&mut f));
+            s.push('\n');
+        }
+        s
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Grid<T> {
+        Grid { width, height, cells: vec![fill; width * height] }
+    }
+}
+
+impl<T> Index<Position> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, position: Position) -> &T {
+        match self.get(position) {
+            Some(cell) => cell,
+            None => panic!("{:?} is outside a {}x{} grid", position, self.width, self.height),
+        }
+    }
+}
+
+impl<T> IndexMut<Position> for Grid<T> {
+    fn index_mut(&mut self, position: Position) -> &mut T {
+        let (width, height) = (self.width, self.height);
+        match self.get_mut(position) {
+            Some(cell) => cell,
+            None => panic!("{:?} is outside a {}x{} grid", position, width, height),
+        }
+    }
+}
+
+impl<T: Display> Display for Grid<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
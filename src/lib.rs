@@ -0,0 +1,42 @@
+pub mod animation;
+pub mod answer;
+pub mod automaton;
+pub mod bench;
+pub mod crosscheck;
+pub mod day1;
+pub mod day2;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day17;
+pub mod day18;
+pub mod day19;
+pub mod day20;
+#[cfg(test)]
+mod examples;
+pub mod fuzz;
+pub mod geometry;
+pub mod grid;
+pub mod memory;
+pub mod output;
+pub mod parse;
+pub mod parse_error;
+pub mod pathfinding;
+pub mod picture;
+pub mod random;
+pub mod repl;
+pub mod runner;
+pub mod solver;
+pub mod verify;
+pub mod watch;
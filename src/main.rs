@@ -1,31 +1,27 @@
 use std::fs::read_to_string;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
+use color_eyre::eyre::eyre;
 use color_eyre::Report;
 use structopt::StructOpt;
-use tracing::debug;
-use tracing_subscriber::EnvFilter;
-
-mod day1;
-mod day2;
-mod day3;
-mod day4;
-mod day5;
-mod day6;
-mod day7;
-mod day8;
-mod day9;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day14;
-mod day15;
-mod day16;
-mod day17;
-mod day18;
-mod day19;
-mod day20;
+use tracing::{debug, info, warn};
+use tracing_chrome::{ChromeLayerBuilder, FlushGuard};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, EnvFilter};
+
+use advent21::animation;
+use advent21::answer::Answer;
+use advent21::output::{self, Format};
+use advent21::picture::ColorMap;
+use advent21::random::Rng;
+use advent21::runner::{self, DayRun};
+use advent21::{bench, crosscheck, fuzz, repl, solver, verify, watch};
+
+#[cfg(feature = "count-allocations")]
+#[global_allocator]
+static ALLOCATOR: advent21::memory::CountingAllocator = advent21::memory::CountingAllocator;
 
 #[derive(Debug, StructOpt)]
 #[structopt()]
@@ -34,13 +30,175 @@ struct Options {
     debug: bool,
 
     #[structopt(short, long)]
-    puzzle: u32,
+    puzzle: Option<u32>,
 
     #[structopt(short, long, parse(from_os_str))]
-    input: PathBuf
+    input: Option<PathBuf>,
+
+    /// Write results to stdout as table, json, ndjson or csv
+    #[structopt(short, long)]
+    format: Option<Format>,
+
+    /// Also draw the puzzle's state to this file, as png, svg or pbm depending on its extension
+    #[structopt(long, parse(from_os_str))]
+    image: Option<PathBuf>,
+
+    /// Pixels across each grid cell in the image
+    #[structopt(long, default_value = "4")]
+    scale: usize,
+
+    /// Colour the image with heat, labels or mono, rather than whatever suits the day
+    #[structopt(long)]
+    colors: Option<ColorMap>,
+
+    /// Watch the puzzle's state change step by step in the terminal before solving it
+    #[structopt(long)]
+    animate: bool,
+
+    /// Steps a second when animating
+    #[structopt(long, default_value = "10")]
+    fps: f64,
+
+    /// Keep running the puzzle again whenever its input changes, showing how the answers changed
+    #[structopt(long)]
+    watch: bool,
+
+    /// When watching, also run the day's examples from this directory
+    #[structopt(long, parse(from_os_str))]
+    examples: Option<PathBuf>,
+
+    /// How often to check for changes when watching, in milliseconds
+    #[structopt(long, default_value = "500")]
+    poll_ms: u64,
+
+    /// Record the parse and part spans to this file as a Chrome trace, for chrome://tracing or Perfetto
+    #[structopt(long, parse(from_os_str))]
+    trace_out: Option<PathBuf>,
+
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Run every registered day against its input in the data directory and print a summary
+    RunAll {
+        #[structopt(long, default_value = "data", parse(from_os_str))]
+        data_dir: PathBuf,
+
+        /// How many days to run at once; defaults to the number of CPUs
+        #[structopt(short, long)]
+        jobs: Option<usize>,
+    },
+
+    /// Time parsing and both parts of one day over many runs, comparing with a saved baseline
+    Bench {
+        #[structopt(short, long)]
+        puzzle: u32,
+
+        /// Defaults to the day's input in the data directory
+        #[structopt(short, long, parse(from_os_str))]
+        input: Option<PathBuf>,
+
+        /// Untimed runs to do first
+        #[structopt(long, default_value = "3")]
+        warmup: usize,
+
+        /// Timed runs
+        #[structopt(long, default_value = "20")]
+        samples: usize,
+
+        /// Where the baseline is kept; defaults to bench/dayN.json
+        #[structopt(long, parse(from_os_str))]
+        baseline: Option<PathBuf>,
+
+        /// Save these timings as the new baseline
+        #[structopt(long)]
+        save: bool,
+    },
+
+    /// Parse one input and explore it interactively: solve either part, step through it, or change settings
+    Repl {
+        #[structopt(short, long)]
+        puzzle: u32,
+
+        /// Defaults to the day's input in the data directory
+        #[structopt(short, long, parse(from_os_str))]
+        input: Option<PathBuf>,
+    },
+
+    /// Feed days mangled versions of their examples, saving any input that makes one panic
+    Fuzz {
+        /// Only fuzz this day
+        #[structopt(short, long)]
+        puzzle: Option<u32>,
+
+        /// Inputs to try on each day
+        #[structopt(long, default_value = "2000")]
+        runs: usize,
+
+        #[structopt(long, default_value = "0")]
+        seed: u64,
+
+        /// How long one input may take before it counts as a hang, in milliseconds
+        #[structopt(long, default_value = "2000")]
+        timeout_ms: u64,
+
+        #[structopt(long, default_value = "data", parse(from_os_str))]
+        examples: PathBuf,
+
+        /// Where inputs that panic are saved
+        #[structopt(long, default_value = "fuzz/corpus", parse(from_os_str))]
+        corpus: PathBuf,
+    },
+
+    /// Write a random input for one day to stdout, the same one every time for a given seed
+    Generate {
+        #[structopt(short, long)]
+        puzzle: u32,
+
+        #[structopt(long, default_value = "0")]
+        seed: u64,
+
+        /// Roughly how many lines, or how wide a grid, depending on the day; defaults to about a real input's
+        #[structopt(long)]
+        size: Option<usize>,
+    },
+
+    /// Compare the days that take shortcuts with slow reference versions, on generated inputs and the examples
+    Crosscheck {
+        /// Only check this day
+        #[structopt(short, long)]
+        puzzle: Option<u32>,
+
+        /// Generated inputs to try on each day, one for each seed starting from --seed
+        #[structopt(long, default_value = "20")]
+        runs: usize,
+
+        #[structopt(long, default_value = "0")]
+        seed: u64,
+
+        /// How big to make the generated inputs, as for generate
+        #[structopt(long)]
+        size: Option<usize>,
+
+        #[structopt(long, default_value = "data", parse(from_os_str))]
+        examples: PathBuf,
+    },
+
+    /// Run the solvers against the inputs listed in an answers file and report any wrong answers
+    Verify {
+        #[structopt(long, default_value = "answers.toml", parse(from_os_str))]
+        answers: PathBuf,
+
+        /// Only verify this day
+        #[structopt(short, long)]
+        puzzle: Option<u32>,
+    },
 }
 
-fn set_up_logging(debug: bool) -> Result<(), Report> {
+/// Everything recorded for `--trace-out` is written when the returned guard is dropped
+fn set_up_logging(debug: bool, trace_out: Option<&Path>) -> Result<Option<FlushGuard>, Report> {
     if std::env::var("RUST_LIB_BACKTRACE").is_err() && debug {
         std::env::set_var("RUST_LIB_BACKTRACE", "full" );
     }
@@ -51,44 +209,223 @@ fn set_up_logging(debug: bool) -> Result<(), Report> {
         std::env::set_var("RUST_LOG", if debug { "debug" } else { "info" });
     }
 
-    tracing_subscriber::fmt::fmt()
-        .with_env_filter(EnvFilter::from_default_env())
+    let (chrome, guard) = match trace_out {
+        Some(path) => {
+            let (layer, guard) = ChromeLayerBuilder::new().file(path.to_string_lossy().into_owned()).include_args(true).build();
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+
+    // Logs go to stderr so that stdout only has results on it
+    tracing_subscriber::registry()
+        .with(EnvFilter::from_default_env())
+        .with(fmt::layer().with_writer(std::io::stderr))
+        .with(chrome)
         .init();
 
+    Ok(guard)
+}
+
+fn report_answer(day: u32, part: u32, answer: &Answer) {
+    if answer.is_multi_line() {
+        info!(day=day, part=part, "answer:\n{}", answer);
+    } else {
+        info!(day=day, part=part, answer=%answer);
+    }
+}
+
+fn run_one(puzzle: u32, input: &Path) -> Result<(), Report> {
+    let input_text = read_to_string(input)?;
+
+    debug!("{file:?}: read {count} bytes", file=input, count=input_text.len());
+
+    let solver = solver::get(puzzle)?;
+    debug!("day {day}: {title}", day=solver.day(), title=solver.title());
+
+    let parsed = solver.parse(&input_text)?;
+    report_answer(solver.day(), 1, &solver.part1(&parsed)?);
+    report_answer(solver.day(), 2, &solver.part2(&parsed)?);
+
     Ok(())
 }
 
-fn main() -> Result<(), Report> {
-    let options = Options::from_args();
-    set_up_logging(options.debug)?;
-
-    let input = read_to_string(&options.input)?;
-
-    debug!("{file:?}: read {count} bytes", file=options.input, count=input.len());
-
-    match options.puzzle {
-        1 => day1::solve(input)?,
-        2 => day2::solve(input)?,
-        3 => day3::solve(input)?,
-        4 => day4::solve(input)?,
-        5 => day5::solve(input)?,
-        6 => day6::solve(input)?,
-        7 => day7::solve(input)?,
-        8 => day8::solve(input)?,
-        9 => day9::solve(input)?,
-        10 => day10::solve(input)?,
-        11 => day11::solve(input)?,
-        12 => day12::solve(input)?,
-        13 => day13::solve(input)?,
-        14 => day14::solve(input)?,
-        15 => day15::solve(input)?,
-        16 => day16::solve(input)?,
-        17 => day17::solve(input)?,
-        18 => day18::solve(input)?,
-        19 => day19::solve(input)?,
-        20 => day20::solve(input)?,
-        _ => panic!("No such puzzle: {day}", day=options.puzzle)
+fn draw_picture(puzzle: u32, input: &Path, image: &Path, scale: usize, colors: Option<ColorMap>) -> Result<(), Report> {
+    let solver = solver::get(puzzle)?;
+    let parsed = solver.parse(&read_to_string(input)?)?;
+    let picture = solver.picture(&parsed, colors)?
+        .ok_or_else(|| eyre!("Day {day} has nothing to draw", day=puzzle))?
+        .with_scale(scale);
+    picture.save(image)?;
+
+    info!("wrote a {}x{} picture to {:?}", picture.width(), picture.height(), image);
+    Ok(())
+}
+
+fn animate(puzzle: u32, input: &Path, fps: f64) -> Result<(), Report> {
+    let solver = solver::get(puzzle)?;
+    let parsed = solver.parse(&read_to_string(input)?)?;
+    let mut simulation = solver.simulation(&parsed)?
+        .ok_or_else(|| eyre!("Day {day} has nothing to animate", day=puzzle))?;
+    let steps = animation::play(solver.title(), simulation.as_mut(), fps)?;
+
+    debug!("animated {} steps", steps);
+    Ok(())
+}
+
+fn run_fuzz(only_day: Option<u32>, options: &fuzz::Options) -> Result<(), Report> {
+    let solvers = match only_day {
+        Some(day) => vec![solver::get(day)?],
+        None => solver::SOLVERS.to_vec(),
+    };
+
+    let mut findings = 0;
+    for solver in solvers {
+        let found = fuzz::fuzz(solver, options)?;
+        println!("day {:>2}: {} inputs, {} problems", solver.day(), options.runs, found.len());
+        for finding in &found {
+            println!("  {}\n    saved to {}", finding.outcome, finding.saved.display());
+        }
+        findings += found.len();
+    }
+
+    match findings {
+        0 => Ok(()),
+        n => Err(eyre!("{} problems found", n)),
+    }
+}
+
+fn run_crosscheck(only_day: Option<u32>, options: &crosscheck::Options) -> Result<(), Report> {
+    let solvers = match only_day {
+        Some(day) => vec![solver::get(day)?],
+        None => solver::SOLVERS.to_vec(),
+    };
+
+    let mut disagreements = 0;
+    for solver in solvers {
+        let summary = crosscheck::crosscheck(solver, options)?;
+        if summary.compared == 0 {
+            debug!("day {} has no reference to check against", solver.day());
+            continue
+        }
+        println!("day {:>2}: {} answers compared, {} disagreements", solver.day(), summary.compared, summary.disagreements.len());
+        for disagreement in &summary.disagreements {
+            println!("  {}", disagreement);
+        }
+        disagreements += summary.disagreements.len();
+    }
+
+    match disagreements {
+        0 => Ok(()),
+        n => Err(eyre!("{} disagreements found", n)),
+    }
+}
+
+fn run_bench(puzzle: u32, input: Option<PathBuf>, warmup: usize, samples: usize, baseline: Option<PathBuf>, save: bool) -> Result<(), Report> {
+    let solver = solver::get(puzzle)?;
+    let input = input.unwrap_or_else(|| runner::default_input(Path::new("data"), puzzle));
+    let baseline_path = baseline.unwrap_or_else(|| bench::default_baseline(puzzle));
+
+    let result = bench::bench(solver, &input, warmup, samples)?;
+    let previous = bench::load_baseline(&baseline_path)?;
+    if let Some(previous) = &previous {
+        if previous.input != result.input {
+            warn!("the baseline was measured on {:?}, not {:?}", previous.input, result.input);
+        }
+    }
+
+    println!("day {}: {} ({}), {} samples after {} warmup runs", puzzle, solver.title(), input.display(), result.parse.samples, warmup);
+    print!("{}", bench::report(&result, previous.as_ref()));
+
+    if save {
+        bench::save_baseline(&baseline_path, &result)?;
+        info!("saved the baseline to {:?}", baseline_path);
+    }
+    Ok(())
+}
+
+fn check_runs(runs: &[DayRun]) -> Result<(), Report> {
+    let failures = runs.iter().filter(|r| !r.is_ok()).count();
+    if failures > 0 {
+        return Err(eyre!("{} of {} days failed", failures, runs.len()))
     }
 
     Ok(())
 }
+
+fn write_results(runs: &[DayRun], format: Format) -> Result<(), Report> {
+    let stdout = std::io::stdout();
+    output::write_runs(&mut stdout.lock(), runs, format)?;
+
+    check_runs(runs)
+}
+
+fn run_all(data_dir: &Path, format: Format, jobs: Option<usize>) -> Result<(), Report> {
+    let days: Vec<_> = solver::SOLVERS.iter()
+        .map(|solver| (*solver, runner::default_input(data_dir, solver.day())))
+        .collect();
+    let jobs = jobs.unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()));
+    let (runs, totals) = runner::run_days(&days, jobs);
+
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    output::write_runs(&mut out, &runs, format)?;
+    // Machine-readable formats only have records on stdout, so the totals go to the log instead
+    match format {
+        Format::Table => write!(out, "{}", output::totals_rows(&totals))?,
+        _ => info!(jobs=totals.jobs, wall=%output::format_duration(totals.wall),
+                   cpu=%totals.cpu.map_or("unknown".to_string(), output::format_duration), "totals"),
+    }
+
+    check_runs(&runs)
+}
+
+fn main() -> Result<(), Report> {
+    let options = Options::from_args();
+    let _trace = set_up_logging(options.debug, options.trace_out.as_deref())?;
+
+    match (options.command, options.puzzle, options.input) {
+        (Some(Command::RunAll { data_dir, jobs }), _, _) => run_all(&data_dir, options.format.unwrap_or(Format::Table), jobs),
+        (Some(Command::Bench { puzzle, input, warmup, samples, baseline, save }), _, _) => run_bench(puzzle, input, warmup, samples, baseline, save),
+        (Some(Command::Repl { puzzle, input }), _, _) => {
+            let solver = solver::get(puzzle)?;
+            let input = input.unwrap_or_else(|| runner::default_input(Path::new("data"), puzzle));
+            repl::repl(solver, &input)
+        }
+        (Some(Command::Fuzz { puzzle, runs, seed, timeout_ms, examples, corpus }), _, _) => {
+            let options = fuzz::Options { runs, seed, timeout: Duration::from_millis(timeout_ms), examples, corpus };
+            run_fuzz(puzzle, &options)
+        }
+        (Some(Command::Generate { puzzle, seed, size }), _, _) => {
+            let solver = solver::get(puzzle)?;
+            print!("{}", solver.generate(&mut Rng::new(seed), size));
+            Ok(())
+        }
+        (Some(Command::Crosscheck { puzzle, runs, seed, size, examples }), _, _) => {
+            run_crosscheck(puzzle, &crosscheck::Options { runs, seed, size, examples })
+        }
+        (Some(Command::Verify { answers, puzzle }), _, _) => verify::verify(&answers, puzzle),
+        (None, Some(puzzle), Some(input)) if options.watch => {
+            let solver = solver::get(puzzle)?;
+            let interval = Duration::from_millis(options.poll_ms);
+            watch::watch(solver, &input, options.examples.as_deref(), interval, &mut std::io::stdout())
+        }
+        (None, Some(puzzle), Some(input)) => {
+            if options.animate {
+                animate(puzzle, &input, options.fps)?
+            }
+            match options.format {
+                Some(format) => {
+                    let solver = solver::get(puzzle)?;
+                    write_results(&[runner::run_day(solver, &input)], format)?
+                }
+                None => run_one(puzzle, &input)?,
+            }
+            match options.image {
+                Some(image) => draw_picture(puzzle, &input, &image, options.scale, options.colors),
+                None => Ok(()),
+            }
+        }
+        (None, _, _) => Err(eyre!("Either --puzzle and --input, or a subcommand, are required")),
+    }
+}
@@ -0,0 +1,119 @@
+//! Counting how much each solver allocates.
+//!
+//! Install [`CountingAllocator`] as the global allocator (the binary does this with the
+//! `count-allocations` feature) and [`measure`] reports what a closure allocated. Counts are kept per
+//! thread, so days running in parallel don't get mixed up.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::Serialize;
+
+/// What was allocated while running something
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Serialize)]
+pub struct AllocStats {
+    /// Every allocation and reallocation
+    pub allocations: u64,
+    pub bytes: u64,
+    /// The most that was allocated and not yet freed at any one time
+    pub peak_bytes: u64,
+}
+
+/// Running totals for one thread
+#[derive(Debug, Copy, Clone)]
+struct Counters {
+    allocations: u64,
+    bytes: u64,
+    live: u64,
+    peak: u64,
+}
+
+thread_local! {
+    static COUNTERS: Cell<Counters> = const { Cell::new(Counters { allocations: 0, bytes: 0, live: 0, peak: 0 }) };
+}
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+fn update(f: impl FnOnce(&mut Counters)) {
+    // Allocations can still happen while a thread's locals are being torn down; those go uncounted
+    let _ = COUNTERS.try_with(|cell| {
+        let mut counters = cell.get();
+        f(&mut counters);
+        cell.set(counters);
+    });
+}
+
+fn allocated(size: usize) {
+    INSTALLED.store(true, Ordering::Relaxed);
+    update(|c| {
+        c.allocations += 1;
+        c.bytes += size as u64;
+        c.live += size as u64;
+        c.peak = c.peak.max(c.live);
+    });
+}
+
+fn freed(size: usize) {
+    // Memory freed on a different thread from the one that allocated it would otherwise underflow
+    update(|c| c.live = c.live.saturating_sub(size as u64));
+}
+
+/// The system allocator, keeping count of what each thread allocates
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() { allocated(layout.size()) }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() { allocated(layout.size()) }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        freed(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            freed(layout.size());
+            allocated(new_size);
+        }
+        new_ptr
+    }
+}
+
+/// Whether `CountingAllocator` is the global allocator, so there's anything to measure
+pub fn is_counting() -> bool {
+    INSTALLED.load(Ordering::Relaxed)
+}
+
+/// Runs `f`, returning what it allocated on this thread if allocations are being counted
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Option<AllocStats>) {
+    let before = COUNTERS.with(|cell| {
+        let before = cell.get();
+        cell.set(Counters { peak: before.live, ..before });
+        before
+    });
+    let result = f();
+    let after = COUNTERS.with(|cell| {
+        let after = cell.get();
+        // Put back the peak from before, so measurements can be nested
+        cell.set(Counters { peak: after.peak.max(before.peak), ..after });
+        after
+    });
+
+    let stats = AllocStats {
+        allocations: after.allocations - before.allocations,
+        bytes: after.bytes - before.bytes,
+        peak_bytes: after.peak.saturating_sub(before.live),
+    };
+    (result, is_counting().then_some(stats))
+}
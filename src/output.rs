@@ -0,0 +1,233 @@
+use std::io::Write;
+use std::str::FromStr;
+use std::time::Duration;
+
+use color_eyre::eyre::eyre;
+use color_eyre::Report;
+use serde::Serialize;
+
+use crate::answer::Answer;
+use crate::memory::AllocStats;
+use crate::runner::{DayRun, Totals};
+
+/// How results are written to stdout
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Format {
+    Table,
+    Json,
+    Ndjson,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Format, Report> {
+        match s {
+            "table" => Ok(Format::Table),
+            "json" => Ok(Format::Json),
+            "ndjson" => Ok(Format::Ndjson),
+            "csv" => Ok(Format::Csv),
+            other => Err(eyre!("Unknown output format {} (expected table, json, ndjson or csv)", other)),
+        }
+    }
+}
+
+/// One day/part result, flattened for machine-readable output
+#[derive(Debug, Serialize)]
+pub struct Record<'a> {
+    pub day: u32,
+    pub title: &'static str,
+    pub part: u32,
+    pub input: String,
+    pub answer: Option<&'a Answer>,
+    pub error: Option<String>,
+    pub parse_ns: Option<u128>,
+    pub time_ns: Option<u128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parse_memory: Option<AllocStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<AllocStats>,
+}
+
+/// Flattens runs into one record per day and part; a day that failed to parse gets an error record for each part
+pub fn records(runs: &[DayRun]) -> Vec<Record<'_>> {
+    let mut records = vec![];
+    for run in runs {
+        let record = |part: u32| Record {
+            day: run.day,
+            title: run.title,
+            part,
+            input: run.input.display().to_string(),
+            answer: None,
+            error: None,
+            parse_ns: run.parse.as_ref().ok().map(|t| t.as_nanos()),
+            time_ns: None,
+            parse_memory: run.parse_memory,
+            memory: None,
+        };
+
+        match &run.parse {
+            Err(e) => {
+                for part in 1..=2 {
+                    records.push(Record { error: Some(format!("{:#}", e)), ..record(part) });
+                }
+            }
+            Ok(_) => {
+                for part in &run.parts {
+                    let (answer, error) = match &part.answer {
+                        Ok(answer) => (Some(answer), None),
+                        Err(e) => (None, Some(format!("{:#}", e))),
+                    };
+                    records.push(Record { answer, error, time_ns: Some(part.time.as_nanos()), memory: part.memory, ..record(part.part) });
+                }
+            }
+        }
+    }
+    records
+}
+
+pub fn format_duration(d: Duration) -> String {
+    let micros = d.as_secs_f64() * 1_000_000.0;
+    if micros < 1_000.0 {
+        format!("{:.1} µs", micros)
+    } else if micros < 1_000_000.0 {
+        format!("{:.2} ms", micros / 1_000.0)
+    } else {
+        format!("{:.2} s", micros / 1_000_000.0)
+    }
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    let bytes = bytes as f64;
+    if bytes < 1024.0 {
+        format!("{} B", bytes)
+    } else if bytes < 1024.0 * 1024.0 {
+        format!("{:.1} KiB", bytes / 1024.0)
+    } else if bytes < 1024.0 * 1024.0 * 1024.0 {
+        format!("{:.1} MiB", bytes / (1024.0 * 1024.0))
+    } else {
+        format!("{:.2} GiB", bytes / (1024.0 * 1024.0 * 1024.0))
+    }
+}
+
+const ANSWER_WIDTH: usize = 40;
+const TABLE_WIDTH: usize = 3 + 2 + 5 + 2 + ANSWER_WIDTH + 2 + 10;
+const MEMORY_WIDTH: usize = 3 * (2 + 11);
+
+/// The allocation columns, which are only shown when allocations were counted
+fn memory_columns(memory: Option<&AllocStats>) -> String {
+    match memory {
+        Some(m) => format!("  {:>11}  {:>11}  {:>11}", m.allocations, format_bytes(m.bytes), format_bytes(m.peak_bytes)),
+        None => String::new(),
+    }
+}
+
+/// Renders the results of a run as a table with one row for parsing and one for each part
+pub fn summary_table(runs: &[DayRun]) -> String {
+    let counted = runs.iter().any(|r| r.parse_memory.is_some());
+    let rule_width = if counted { TABLE_WIDTH + MEMORY_WIDTH } else { TABLE_WIDTH };
+
+    let mut table = format!("{:>3}  {:<5}  {:<width$}  {:>10}", "Day", "Part", "Answer", "Time", width=ANSWER_WIDTH);
+    if counted {
+        table.push_str(&format!("  {:>11}  {:>11}  {:>11}", "Allocations", "Allocated", "Peak"));
+    }
+    table.push('\n');
+    table.push_str(&format!("{}\n", "-".repeat(rule_width)));
+
+    for run in runs {
+        match &run.parse {
+            Ok(time) => {
+                let description = format!("{} ({})", run.title, run.input.display());
+                table.push_str(&format!("{:>3}  {:<5}  {:<width$}  {:>10}{}\n", run.day, "parse", description, format_duration(*time),
+                                        memory_columns(run.parse_memory.as_ref()), width=ANSWER_WIDTH))
+            }
+            Err(e) => table.push_str(&format!("{:>3}  {:<5}  {:<width$}\n", run.day, "parse", format!("error: {:#}", e), width=ANSWER_WIDTH)),
+        }
+
+        for part in &run.parts {
+            let answer = match &part.answer {
+                Ok(answer) => answer.to_string(),
+                Err(e) => format!("error: {:#}", e),
+            };
+            let mut rows = answer.split('\n');
+            table.push_str(&format!("{:>3}  {:<5}  {:<width$}  {:>10}{}\n", run.day, part.part, rows.next().unwrap_or_default(), format_duration(part.time),
+                                    memory_columns(part.memory.as_ref()), width=ANSWER_WIDTH));
+            for row in rows {
+                table.push_str(&format!("{:>3}  {:<5}  {}\n", "", "", row));
+            }
+        }
+    }
+
+    let total: Duration = runs.iter().map(|r| r.total_time()).sum();
+    table.push_str(&format!("{}\n", "-".repeat(rule_width)));
+    table.push_str(&format!("{:>3}  {:<5}  {:<width$}  {:>10}\n", "", "", "total", format_duration(total), width=ANSWER_WIDTH));
+    table
+}
+
+/// Rows for the end of the summary table saying how long the whole batch took
+pub fn totals_rows(totals: &Totals) -> String {
+    let mut rows = format!("{:>3}  {:<5}  {:<width$}  {:>10}\n", "", "", format!("wall clock ({} jobs)", totals.jobs), format_duration(totals.wall), width=ANSWER_WIDTH);
+    if let Some(cpu) = totals.cpu {
+        rows.push_str(&format!("{:>3}  {:<5}  {:<width$}  {:>10}\n", "", "", "cpu time", format_duration(cpu), width=ANSWER_WIDTH));
+    }
+    rows
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
+fn write_csv(out: &mut impl Write, records: &[Record]) -> Result<(), Report> {
+    // The allocation columns are only there when allocations were counted
+    let counted = records.iter().any(|r| r.parse_memory.is_some());
+    write!(out, "day,title,part,input,answer,error,parse_ns,time_ns")?;
+    if counted {
+        write!(out, ",parse_allocations,parse_bytes,parse_peak_bytes,allocations,bytes,peak_bytes")?;
+    }
+    writeln!(out)?;
+    for r in records {
+        let optional = |n: Option<u128>| n.map(|n| n.to_string()).unwrap_or_default();
+        write!(out, "{},{},{},{},{},{},{},{}",
+                 r.day,
+                 csv_field(r.title),
+                 r.part,
+                 csv_field(&r.input),
+                 csv_field(&r.answer.map(|a| a.to_string()).unwrap_or_default()),
+                 csv_field(r.error.as_deref().unwrap_or_default()),
+                 optional(r.parse_ns),
+                 optional(r.time_ns))?;
+        if counted {
+            let memory = |m: Option<AllocStats>| match m {
+                Some(m) => format!("{},{},{}", m.allocations, m.bytes, m.peak_bytes),
+                None => ",,".to_owned(),
+            };
+            write!(out, ",{},{}", memory(r.parse_memory), memory(r.memory))?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Writes the results of a run to `out` in the given format
+pub fn write_runs(out: &mut impl Write, runs: &[DayRun], format: Format) -> Result<(), Report> {
+    match format {
+        Format::Table => write!(out, "{}", summary_table(runs))?,
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut *out, &records(runs))?;
+            writeln!(out)?;
+        }
+        Format::Ndjson => {
+            for record in records(runs) {
+                serde_json::to_writer(&mut *out, &record)?;
+                writeln!(out)?;
+            }
+        }
+        Format::Csv => write_csv(out, &records(runs))?,
+    }
+    Ok(())
+}
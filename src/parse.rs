@@ -0,0 +1,134 @@
+//! nom combinators shared by the puzzle parsers.
+//!
+//! List combinators stop at a blank line or the end of the input. Once a separator has been
+//! followed by more text they're committed to it, so a malformed line is reported where it is
+//! rather than being left over for `parse_all` to complain about.
+
+use nom::character::complete::{char, newline, one_of, space0};
+use nom::combinator::consumed;
+use nom::error::{VerboseError, VerboseErrorKind};
+use nom::multi::{many1, separated_list1};
+use nom::sequence::{delimited, tuple};
+
+use crate::grid::Grid;
+use crate::parse_error::{ParseError, ParseResult};
+
+/// Parses the whole of `input` with `parser`, allowing trailing whitespace
+pub fn parse_all<'a, T>(input: &'a str, mut parser: impl FnMut(&'a str) -> ParseResult<'a, T>) -> Result<T, ParseError> {
+    match parser(input) {
+        Ok((leftovers, result)) if leftovers.trim().is_empty() => Ok(result),
+        Ok((leftovers, _)) => {
+            let at_line_start = input.len() == leftovers.len() || input[..input.len() - leftovers.len()].ends_with('\n');
+            Err(ParseError::at(input, leftovers, if at_line_start { "end of input" } else { "end of line" }))
+        }
+        Err(e) => Err(ParseError::from_nom(input, e)),
+    }
+}
+
+fn commit<T>(result: ParseResult<'_, T>) -> ParseResult<'_, T> {
+    result.map_err(|e| match e {
+        nom::Err::Error(e) => nom::Err::Failure(e),
+        other => other,
+    })
+}
+
+/// One item per line, at least one of them
+pub fn lines<'a, O>(mut item: impl FnMut(&'a str) -> ParseResult<'a, O>) -> impl FnMut(&'a str) -> ParseResult<'a, Vec<O>> {
+    move |i: &'a str| {
+        let (mut rest, first) = item(i)?;
+        let mut items = vec![first];
+        loop {
+            match rest.strip_prefix('\n') {
+                Some(next) if !next.is_empty() && !next.starts_with('\n') => {
+                    let (after, parsed) = commit(item(next))?;
+                    items.push(parsed);
+                    rest = after;
+                }
+                _ => return Ok((rest, items)),
+            }
+        }
+    }
+}
+
+/// Blocks of lines separated by one or more blank lines
+pub fn sections<'a, O>(mut section: impl FnMut(&'a str) -> ParseResult<'a, O>) -> impl FnMut(&'a str) -> ParseResult<'a, Vec<O>> {
+    move |i: &'a str| {
+        let (mut rest, first) = section(i)?;
+        let mut sections = vec![first];
+        loop {
+            let next = rest.trim_start_matches('\n');
+            if rest.len() - next.len() < 2 || next.is_empty() {
+                return Ok((rest, sections))
+            }
+            let (after, parsed) = commit(section(next))?;
+            sections.push(parsed);
+            rest = after;
+        }
+    }
+}
+
+/// The end of a line followed by at least one blank line
+pub fn section_break(i: &str) -> ParseResult<'_, ()> {
+    tuple((newline, many1(newline)))(i).map(|(left, _)| (left, ()))
+}
+
+/// Numbers separated by commas, as in `3,4,3,1,2`
+pub fn comma_separated<'a, O>(number: impl FnMut(&'a str) -> ParseResult<'a, O>) -> impl FnMut(&'a str) -> ParseResult<'a, Vec<O>> {
+    separated_list1(delimited(space0, char(','), space0), number)
+}
+
+/// An `x,y` pair
+pub fn coordinate_pair<'a, O>(mut number: impl FnMut(&'a str) -> ParseResult<'a, O>) -> impl FnMut(&'a str) -> ParseResult<'a, (O, O)> {
+    move |i: &'a str| {
+        let (i, x) = number(i)?;
+        let (i, _) = delimited(space0, char(','), space0)(i)?;
+        let (i, y) = number(i)?;
+        Ok((i, (x, y)))
+    }
+}
+
+/// An `x,y,z` triple
+pub fn coordinate_triple<'a, O>(mut number: impl FnMut(&'a str) -> ParseResult<'a, O>) -> impl FnMut(&'a str) -> ParseResult<'a, (O, O, O)> {
+    move |i: &'a str| {
+        let (i, x) = number(i)?;
+        let (i, _) = delimited(space0, char(','), space0)(i)?;
+        let (i, y) = number(i)?;
+        let (i, _) = delimited(space0, char(','), space0)(i)?;
+        let (i, z) = number(i)?;
+        Ok((i, (x, y, z)))
+    }
+}
+
+/// A single decimal digit as its value
+pub fn digit(i: &str) -> ParseResult<'_, u8> {
+    one_of("0123456789")(i).map(|(left, c)| (left, c as u8 - b'0'))
+}
+
+/// Rows of cells, one character each, with every row the same width
+pub fn char_grid<'a, O>(cell: impl FnMut(&'a str) -> ParseResult<'a, O>) -> impl FnMut(&'a str) -> ParseResult<'a, Vec<Vec<O>>> {
+    let mut rows = lines(consumed(many1(cell)));
+    move |i: &'a str| {
+        let (rest, rows) = rows(i)?;
+        let width = rows[0].1.len();
+        if let Some((text, _)) = rows.iter().find(|(_, row)| row.len() != width) {
+            let offset = text.char_indices().nth(width).map_or(text.len(), |(offset, _)| offset);
+            let error = VerboseError { errors: vec![(&text[offset..], VerboseErrorKind::Context("row as wide as the first"))] };
+            return Err(nom::Err::Failure(error))
+        }
+        Ok((rest, rows.into_iter().map(|(_, row)| row).collect()))
+    }
+}
+
+/// A `char_grid` as a `Grid`
+pub fn grid<'a, O>(cell: impl FnMut(&'a str) -> ParseResult<'a, O>) -> impl FnMut(&'a str) -> ParseResult<'a, Grid<O>> {
+    let mut rows = char_grid(cell);
+    move |i: &'a str| {
+        let (rest, rows) = rows(i)?;
+        Ok((rest, Grid::from_rows(rows).expect("char_grid only returns rectangular grids")))
+    }
+}
+
+/// A grid of single digits, such as a height map
+pub fn digit_grid(i: &str) -> ParseResult<'_, Grid<u8>> {
+    grid(digit)(i)
+}
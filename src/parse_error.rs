@@ -0,0 +1,112 @@
+use std::fmt::{Display, Formatter};
+
+use nom::error::{ErrorKind, VerboseError, VerboseErrorKind};
+use nom::IResult;
+
+/// The result type for the text parsers: `VerboseError` keeps enough detail to build a `ParseError`
+pub type ParseResult<'a, T> = IResult<&'a str, T, VerboseError<&'a str>>;
+
+/// Where and why a puzzle input failed to parse
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseError {
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column, counted in characters
+    pub column: usize,
+    pub expected: String,
+    pub found: String,
+    /// The offending line with a caret under the column
+    pub snippet: String,
+}
+
+impl ParseError {
+    /// Builds an error at a byte offset into `input`
+    pub fn at_offset(input: &str, offset: usize, expected: impl Into<String>) -> ParseError {
+        let offset = offset.min(input.len());
+        let line_start = input[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = input[offset..].find('\n').map_or(input.len(), |i| offset + i);
+        let line = input[..offset].matches('\n').count() + 1;
+        let text = &input[line_start..line_end];
+        let column = input[line_start..offset].chars().count() + 1;
+
+        let found = match input[offset..].chars().next() {
+            None => "end of input".to_owned(),
+            Some('\n') => "end of line".to_owned(),
+            Some(_) => {
+                let token: String = input[offset..line_end].chars().take_while(|c| !c.is_whitespace()).take(20).collect();
+                format!("{:?}", token)
+            }
+        };
+
+        let gutter = line.to_string().len();
+        let snippet = format!(
+            "{:gutter$} |\n{} | {}\n{:gutter$} | {}^",
+            "", line, text, "", " ".repeat(column - 1), gutter=gutter
+        );
+
+        ParseError { line, column, expected: expected.into(), found, snippet }
+    }
+
+    /// Builds an error at the point where `remaining` starts, which must be a slice of `input`
+    pub fn at(input: &str, remaining: &str, expected: impl Into<String>) -> ParseError {
+        ParseError::at_offset(input, offset_of(input, remaining), expected)
+    }
+
+    /// Converts a nom error from parsing `input` into a diagnostic
+    pub fn from_nom(input: &str, error: nom::Err<VerboseError<&str>>) -> ParseError {
+        match error {
+            nom::Err::Incomplete(_) => ParseError::at_offset(input, input.len(), "more input"),
+            nom::Err::Error(e) | nom::Err::Failure(e) => {
+                // The first error is the innermost one, which is where parsing actually stopped
+                let (remaining, kind) = match e.errors.first() {
+                    Some(first) => first,
+                    None => return ParseError::at_offset(input, 0, "valid input"),
+                };
+                // A context names what was being parsed, which says more than the kind of token nom wanted
+                let context = e.errors.iter().find_map(|(_, k)| match k { VerboseErrorKind::Context(c) => Some(*c), _ => None });
+                let expected = context.map_or_else(|| describe(kind), |c| format!("a {}", c));
+                ParseError::at(input, remaining, expected)
+            }
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}: expected {}, found {}\n{}", self.line, self.column, self.expected, self.found, self.snippet)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn offset_of(input: &str, remaining: &str) -> usize {
+    let start = input.as_ptr() as usize;
+    let position = remaining.as_ptr() as usize;
+    if position >= start && position <= start + input.len() {
+        position - start
+    } else {
+        // Not actually a slice of the input, so the best guess is that it's a suffix of it
+        input.len().saturating_sub(remaining.len())
+    }
+}
+
+fn describe(kind: &VerboseErrorKind) -> String {
+    match kind {
+        VerboseErrorKind::Char('\n') => "a newline".to_owned(),
+        VerboseErrorKind::Char(c) => format!("{:?}", c),
+        VerboseErrorKind::Context(c) => format!("a {}", c),
+        VerboseErrorKind::Nom(kind) => match kind {
+            ErrorKind::Tag => "a keyword or symbol".to_owned(),
+            ErrorKind::Digit => "a number".to_owned(),
+            ErrorKind::Alpha => "a word".to_owned(),
+            ErrorKind::AlphaNumeric => "a letter or digit".to_owned(),
+            ErrorKind::Space | ErrorKind::MultiSpace => "whitespace".to_owned(),
+            ErrorKind::CrLf => "a newline".to_owned(),
+            ErrorKind::OneOf => "one of a set of characters".to_owned(),
+            ErrorKind::Eof => "end of input".to_owned(),
+            ErrorKind::Count | ErrorKind::Many1 => "more items".to_owned(),
+            other => other.description().to_lowercase(),
+        },
+    }
+}
+
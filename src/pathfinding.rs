@@ -0,0 +1,113 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
+use std::hash::Hash;
+
+use fnv::{FnvHashMap, FnvHashSet};
+
+/// Something that can be searched: a set of nodes joined by weighted, one-way edges
+pub trait Graph {
+    type Node: Copy + Eq + Hash;
+
+    /// The nodes one step away from `node`, along with what it costs to move to each
+    fn neighbors(&self, node: Self::Node) -> impl Iterator<Item = (Self::Node, u64)>;
+}
+
+/// The cheapest way found from a start node to a goal
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Route<N> {
+    pub cost: u64,
+    /// Every node visited, from the start to the goal inclusive
+    pub nodes: Vec<N>,
+}
+
+/// Follows `came_from` links back from `goal`, returning the path from the start to `goal`
+pub fn reconstruct_path<N: Copy + Eq + Hash>(came_from: &FnvHashMap<N, N>, goal: N) -> Vec<N> {
+    let mut path = vec![goal];
+    let mut node = goal;
+    while let Some(&previous) = came_from.get(&node) {
+        path.push(previous);
+        node = previous;
+    }
+    path.reverse();
+    path
+}
+
+/// Finds the path to the nearest goal in number of steps, ignoring edge costs
+pub fn bfs<G: Graph>(graph: &G, start: G::Node, mut is_goal: impl FnMut(G::Node) -> bool) -> Option<Vec<G::Node>> {
+    let mut came_from = FnvHashMap::default();
+    let mut seen = FnvHashSet::default();
+    let mut queue = VecDeque::from([start]);
+    seen.insert(start);
+
+    while let Some(node) = queue.pop_front() {
+        if is_goal(node) {
+            return Some(reconstruct_path(&came_from, node))
+        }
+        for (next, _) in graph.neighbors(node) {
+            if seen.insert(next) {
+                came_from.insert(next, node);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+/// A node waiting to be explored; the heap pops the lowest estimate first
+struct Frontier<N> {
+    estimate: u64,
+    node: N,
+}
+
+impl<N> PartialEq for Frontier<N> {
+    fn eq(&self, other: &Self) -> bool { self.estimate == other.estimate }
+}
+
+impl<N> Eq for Frontier<N> {}
+
+impl<N> PartialOrd for Frontier<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+impl<N> Ord for Frontier<N> {
+    fn cmp(&self, other: &Self) -> Ordering { other.estimate.cmp(&self.estimate) }
+}
+
+/// Finds the cheapest route to a goal. `heuristic` estimates the remaining cost from a node and must never
+/// overestimate it, or the route found might not be the cheapest.
+pub fn astar<G: Graph>(
+    graph: &G,
+    start: G::Node,
+    mut is_goal: impl FnMut(G::Node) -> bool,
+    mut heuristic: impl FnMut(G::Node) -> u64,
+) -> Option<Route<G::Node>> {
+    let mut costs = FnvHashMap::default();
+    let mut came_from = FnvHashMap::default();
+    let mut frontier = BinaryHeap::new();
+    costs.insert(start, 0);
+    frontier.push(Frontier { estimate: heuristic(start), node: start });
+
+    while let Some(Frontier { estimate, node }) = frontier.pop() {
+        let cost = costs[&node];
+        if is_goal(node) {
+            return Some(Route { cost, nodes: reconstruct_path(&came_from, node) })
+        }
+        // A cheaper way here was found after this entry was queued, and has already been explored
+        if estimate > cost + heuristic(node) { continue }
+
+        for (next, step) in graph.neighbors(node) {
+            let next_cost = cost + step;
+            if costs.get(&next).is_none_or(|&c| next_cost < c) {
+                costs.insert(next, next_cost);
+                came_from.insert(next, node);
+                frontier.push(Frontier { estimate: next_cost + heuristic(next), node: next });
+            }
+        }
+    }
+    None
+}
+
+/// Finds the cheapest route to a goal, exploring outwards evenly in every direction
+pub fn dijkstra<G: Graph>(graph: &G, start: G::Node, is_goal: impl FnMut(G::Node) -> bool) -> Option<Route<G::Node>> {
+    astar(graph, start, is_goal, |_| 0)
+}
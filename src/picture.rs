@@ -0,0 +1,234 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use color_eyre::eyre::eyre;
+use color_eyre::Report;
+
+use crate::grid::{Grid, Position};
+
+/// An RGB colour
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+/// Colours that are easy to tell apart from each other, for labelling regions
+const LABELS: [Color; 10] = [
+    Color::new(0x1f, 0x77, 0xb4),
+    Color::new(0xff, 0x7f, 0x0e),
+    Color::new(0x2c, 0xa0, 0x2c),
+    Color::new(0xd6, 0x27, 0x28),
+    Color::new(0x94, 0x67, 0xbd),
+    Color::new(0x8c, 0x56, 0x4b),
+    Color::new(0xe3, 0x77, 0xc2),
+    Color::new(0x7f, 0x7f, 0x7f),
+    Color::new(0xbc, 0xbd, 0x22),
+    Color::new(0x17, 0xbe, 0xcf),
+];
+
+impl Color {
+    pub const BLACK: Color = Color::new(0, 0, 0);
+    pub const WHITE: Color = Color::new(0xff, 0xff, 0xff);
+
+    pub const fn new(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b }
+    }
+
+    /// Perceived brightness, from 0 for black to 255 for white
+    pub fn luminance(self) -> u8 {
+        ((299 * self.r as u32 + 587 * self.g as u32 + 114 * self.b as u32) / 1000) as u8
+    }
+
+    /// The colour `t` of the way from this one to `other`
+    pub fn blend(self, other: Color, t: f64) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        Color::new(mix(self.r, other.r), mix(self.g, other.g), mix(self.b, other.b))
+    }
+
+    /// A heat map colour for `value` out of `max`, running from dark blue through red to yellow
+    pub fn heat(value: u32, max: u32) -> Color {
+        let (cold, warm, hot) = (Color::new(0x10, 0x10, 0x40), Color::new(0xc0, 0x30, 0x30), Color::new(0xff, 0xe0, 0x40));
+        let t = if max == 0 { 0.0 } else { value.min(max) as f64 / max as f64 };
+        if t < 0.5 { cold.blend(warm, t * 2.0) } else { warm.blend(hot, t * 2.0 - 1.0) }
+    }
+
+    /// A colour for region number `index`; neighbouring numbers get different colours
+    pub fn label(index: usize) -> Color {
+        LABELS[index % LABELS.len()]
+    }
+
+    /// The colour as `#rrggbb`
+    pub fn hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+/// How a day colours the values in its picture
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ColorMap {
+    /// From dark blue through red to yellow
+    Heat,
+    /// A different colour for each value, for telling regions apart
+    Labels,
+    /// Shades of grey, from black up to white
+    Mono,
+}
+
+impl FromStr for ColorMap {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<ColorMap, Report> {
+        match s {
+            "heat" => Ok(ColorMap::Heat),
+            "labels" => Ok(ColorMap::Labels),
+            "mono" => Ok(ColorMap::Mono),
+            other => Err(eyre!("Unknown colour map {} (expected heat, labels or mono)", other)),
+        }
+    }
+}
+
+impl ColorMap {
+    /// The colour for `value` out of `max`
+    pub fn color(self, value: u32, max: u32) -> Color {
+        match self {
+            ColorMap::Heat => Color::heat(value, max),
+            ColorMap::Labels => Color::label(value as usize),
+            ColorMap::Mono => Color::BLACK.blend(Color::WHITE, if max == 0 { 0.0 } else { value as f64 / max as f64 }),
+        }
+    }
+
+    /// A colour that none of the values get, for picking something out
+    pub fn highlight(self) -> Color {
+        match self {
+            ColorMap::Heat | ColorMap::Labels => Color::WHITE,
+            ColorMap::Mono => Color::label(3),
+        }
+    }
+}
+
+/// The file formats a picture can be written as
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ImageFormat {
+    Png,
+    Svg,
+    /// Black and white only: cells darker than mid-grey are black
+    Pbm,
+}
+
+impl FromStr for ImageFormat {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<ImageFormat, Report> {
+        match s {
+            "png" => Ok(ImageFormat::Png),
+            "svg" => Ok(ImageFormat::Svg),
+            "pbm" => Ok(ImageFormat::Pbm),
+            other => Err(eyre!("Unknown image format {} (expected png, svg or pbm)", other)),
+        }
+    }
+}
+
+impl ImageFormat {
+    /// The format a file should be written in, going by its extension
+    pub fn from_path(path: &Path) -> Result<ImageFormat, Report> {
+        let extension = path.extension().and_then(|e| e.to_str())
+            .ok_or_else(|| eyre!("Can't tell the image format of {:?} without a png, svg or pbm extension", path))?;
+        extension.to_ascii_lowercase().parse()
+    }
+}
+
+/// A grid of coloured cells, each drawn as a square `scale` pixels across
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Picture {
+    cells: Grid<Color>,
+    scale: usize,
+}
+
+impl Picture {
+    /// Colours each cell of `grid` with `color_map`
+    pub fn new<T>(grid: &Grid<T>, mut color_map: impl FnMut(Position, &T) -> Color) -> Picture {
+        let cells = Grid::from_fn(grid.width(), grid.height(), |position| color_map(position, &grid[position]));
+        Picture { cells, scale: 1 }
+    }
+
+    /// The same picture with each cell `scale` pixels across, which must be at least 1
+    pub fn with_scale(self, scale: usize) -> Picture {
+        Picture { scale: scale.max(1), ..self }
+    }
+
+    pub fn cells(&self) -> &Grid<Color> { &self.cells }
+
+    /// The size of the image in pixels
+    pub fn width(&self) -> usize { self.cells.width() * self.scale }
+    pub fn height(&self) -> usize { self.cells.height() * self.scale }
+
+    /// Every row of pixels, top to bottom
+    fn pixel_rows(&self) -> impl Iterator<Item = Vec<Color>> + '_ {
+        self.cells.rows().flat_map(move |row| {
+            let pixels: Vec<_> = row.iter().flat_map(|&c| std::iter::repeat_n(c, self.scale)).collect();
+            std::iter::repeat_n(pixels, self.scale)
+        })
+    }
+
+    pub fn write(&self, out: &mut impl Write, format: ImageFormat) -> Result<(), Report> {
+        match format {
+            ImageFormat::Png => self.write_png(out),
+            ImageFormat::Svg => self.write_svg(out),
+            ImageFormat::Pbm => self.write_pbm(out),
+        }
+    }
+
+    /// Writes the picture to `path`, in the format its extension asks for
+    pub fn save(&self, path: &Path) -> Result<(), Report> {
+        let format = ImageFormat::from_path(path)?;
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write(&mut out, format)?;
+        out.flush()?;
+        Ok(())
+    }
+
+    fn write_png(&self, out: &mut impl Write) -> Result<(), Report> {
+        let mut encoder = png::Encoder::new(out, self.width() as u32, self.height() as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let data: Vec<u8> = self.pixel_rows().flatten().flat_map(|c| [c.r, c.g, c.b]).collect();
+        encoder.write_header()?.write_image_data(&data)?;
+        Ok(())
+    }
+
+    fn write_svg(&self, out: &mut impl Write) -> Result<(), Report> {
+        // The view box is in cells, so the scale only changes the width and height
+        writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}" shape-rendering="crispEdges">"#,
+            self.width(), self.height(), self.cells.width(), self.cells.height()
+        )?;
+        for (y, row) in self.cells.rows().enumerate() {
+            // One rectangle for each run of the same colour keeps the file small
+            let mut x = 0;
+            for run in row.chunk_by(|a, b| a == b) {
+                writeln!(out, r#"<rect x="{}" y="{}" width="{}" height="1" fill="{}"/>"#, x, y, run.len(), run[0].hex())?;
+                x += run.len();
+            }
+        }
+        writeln!(out, "</svg>")?;
+        Ok(())
+    }
+
+    fn write_pbm(&self, out: &mut impl Write) -> Result<(), Report> {
+        // Binary PBM: one bit per pixel with 1 for black, and each row padded out to a whole byte
+        write!(out, "P4\n{} {}\n", self.width(), self.height())?;
+        for row in self.pixel_rows() {
+            let bytes: Vec<u8> = row.chunks(8)
+                .map(|pixels| pixels.iter().enumerate().fold(0, |byte, (i, c)| byte | ((c.luminance() < 128) as u8) << (7 - i)))
+                .collect();
+            out.write_all(&bytes)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+//! A small seedable random number generator, so anything built from random numbers can be repeated exactly.
+
+/// SplitMix64: not for cryptography, but fast and the same on every platform
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number from `0` up to but not including `n`, which mustn't be 0
+    pub fn below(&mut self, n: usize) -> usize {
+        // The bias from taking the remainder is far too small to matter here
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// A number from `low` up to and including `high`
+    pub fn between(&mut self, low: i64, high: i64) -> i64 {
+        low + (self.next_u64() % (high.abs_diff(low) + 1)) as i64
+    }
+
+    /// True with the given probability
+    pub fn chance(&mut self, probability: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        // Fisher-Yates
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// Rows of `height` lines, `width` cells each, as a day's input would lay out a grid
+pub fn grid(rng: &mut Rng, width: usize, height: usize, mut cell: impl FnMut(&mut Rng) -> char) -> String {
+    let mut text = String::with_capacity((width + 1) * height);
+    for _ in 0..height {
+        text.extend((0..width).map(|_| cell(rng)));
+        text.push('\n');
+    }
+    text
+}
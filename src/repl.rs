@@ -0,0 +1,233 @@
+//! Loads one input into a day's model and answers commands about it, so it only needs parsing once.
+
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use color_eyre::eyre::eyre;
+use color_eyre::Report;
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use tracing::warn;
+
+use crate::animation::Simulation;
+use crate::output::format_duration;
+use crate::runner::timed;
+use crate::solver::{Parsed, Solver};
+
+pub const COMMANDS: [&str; 8] = ["part1", "part2", "step", "show", "set", "reset", "help", "quit"];
+
+const HELP: &str = "\
+part1, part2       solve a part of the puzzle
+step [N]           move the day's state on by N steps, or one
+show               draw the day's state, and list its settings
+set PARAM=VALUE    change one of the day's settings
+reset              go back to the state as it was parsed
+help               show this again
+quit               leave (so does Ctrl-D)";
+
+/// One line typed at the prompt
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Command {
+    Part(u32),
+    Step(usize),
+    Show,
+    Set { param: String, value: String },
+    Reset,
+    Help,
+    Quit,
+}
+
+impl FromStr for Command {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, rest) = s.trim().split_once(char::is_whitespace).unwrap_or((s.trim(), ""));
+        let rest = rest.trim();
+        let command = match name {
+            "part1" => Command::Part(1),
+            "part2" => Command::Part(2),
+            "step" if rest.is_empty() => Command::Step(1),
+            "step" => Command::Step(rest.parse().map_err(|_| eyre!("{:?} isn't a number of steps", rest))?),
+            "show" => Command::Show,
+            "set" => {
+                let (param, value) = rest.split_once('=').ok_or_else(|| eyre!("Expected set PARAM=VALUE"))?;
+                return Ok(Command::Set { param: param.trim().to_owned(), value: value.trim().to_owned() })
+            }
+            "reset" => Command::Reset,
+            "help" | "?" => Command::Help,
+            "quit" | "exit" => Command::Quit,
+            _ => return Err(eyre!("Unknown command {:?}; try help", name)),
+        };
+        if !rest.is_empty() && !matches!(command, Command::Step(_)) {
+            return Err(eyre!("{} doesn't take anything after it", name))
+        }
+        Ok(command)
+    }
+}
+
+/// A parsed input, and the state of the day's simulation of it if it has one
+pub struct Session {
+    solver: &'static dyn Solver,
+    parsed: Parsed,
+    simulation: Option<Box<dyn Simulation>>,
+}
+
+impl Session {
+    pub fn new(solver: &'static dyn Solver, input: &str) -> Result<Session, Report> {
+        let parsed = solver.parse(input)?;
+        let simulation = solver.simulation(&parsed)?;
+        Ok(Session { solver, parsed, simulation })
+    }
+
+    pub fn load(solver: &'static dyn Solver, input: &Path) -> Result<Session, Report> {
+        let text = read_to_string(input).map_err(|e| Report::new(e).wrap_err(format!("Couldn't read {}", input.display())))?;
+        Session::new(solver, &text)
+    }
+
+    /// The names of the settings `set` can change
+    pub fn params(&self) -> Vec<&'static str> {
+        self.simulation.iter().flat_map(|s| s.params()).map(|(name, _)| name).collect()
+    }
+
+    fn simulation(&mut self) -> Result<&mut Box<dyn Simulation>, Report> {
+        let day = self.solver.day();
+        self.simulation.as_mut().ok_or_else(|| eyre!("Day {} has no state to step through or show", day))
+    }
+
+    /// Carries out `command`, returning what to print
+    pub fn run(&mut self, command: &Command) -> Result<String, Report> {
+        match command {
+            Command::Part(part) => {
+                let (answer, time) = match part {
+                    1 => timed(|| self.solver.part1(&self.parsed)),
+                    _ => timed(|| self.solver.part2(&self.parsed)),
+                };
+                let answer = answer?.to_string();
+                let separator = if answer.contains('\n') { "\n" } else { " " };
+                Ok(format!("{}{}({})", answer, separator, format_duration(time)))
+            }
+            Command::Step(steps) => {
+                let simulation = self.simulation()?;
+                let mut taken = 0;
+                while taken < *steps && !simulation.is_finished() {
+                    simulation.step();
+                    taken += 1;
+                }
+                let finished = if taken < *steps { ", and there's nothing more to do" } else { "" };
+                Ok(format!("{} step{} taken{}", taken, if taken == 1 { "" } else { "s" }, finished))
+            }
+            Command::Show => {
+                let simulation = self.simulation()?;
+                let mut shown = simulation.frame();
+                for (name, value) in simulation.params() {
+                    shown.push_str(&format!("\n{} = {}", name, value));
+                }
+                Ok(shown)
+            }
+            Command::Set { param, value } => {
+                self.simulation()?.set(param, value)?;
+                Ok(format!("{} = {}", param, value))
+            }
+            Command::Reset => {
+                self.simulation = self.solver.simulation(&self.parsed)?;
+                Ok("Back to the parsed input".to_owned())
+            }
+            Command::Help => Ok(HELP.to_owned()),
+            Command::Quit => Ok(String::new()),
+        }
+    }
+}
+
+/// The candidates for finishing the word before `pos`, and where that word starts: command names first,
+/// then setting names after `set`
+pub fn complete(line: &str, pos: usize, params: &[&str]) -> (usize, Vec<String>) {
+    let line = &line[..pos];
+    let start = line.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+    let word = &line[start..];
+    let candidates: Vec<String> = if start == 0 {
+        COMMANDS.iter().filter(|c| c.starts_with(word)).map(|c| c.to_string()).collect()
+    } else if line.split_whitespace().next() == Some("set") && !word.contains('=') {
+        params.iter().filter(|p| p.starts_with(word)).map(|p| format!("{}=", p)).collect()
+    } else {
+        vec![]
+    };
+    (start, candidates)
+}
+
+struct ReplHelper {
+    params: Vec<&'static str>,
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(complete(line, pos, &self.params))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+/// Where commands are remembered between sessions
+fn history_file() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| Path::new(&home).join(".advent21_history"))
+}
+
+/// Reads commands from the terminal until told to quit
+pub fn repl(solver: &'static dyn Solver, input: &Path) -> Result<(), Report> {
+    let (session, time) = timed(|| Session::load(solver, input));
+    let mut session = session?;
+    println!("Day {}: {}, parsed {} in {}. Type help for commands.",
+             solver.day(), solver.title(), input.display(), format_duration(time));
+
+    let mut editor = Editor::<ReplHelper>::new();
+    editor.set_helper(Some(ReplHelper { params: session.params() }));
+    let history = history_file();
+    if let Some(history) = &history {
+        // There won't be any history the first time
+        let _ = editor.load_history(history);
+    }
+
+    let prompt = format!("day{}> ", solver.day());
+    loop {
+        let line = match editor.readline(&prompt) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+        if line.trim().is_empty() {
+            continue
+        }
+        editor.add_history_entry(line.as_str());
+
+        match line.parse::<Command>() {
+            Ok(Command::Quit) => break,
+            Ok(command) => match session.run(&command) {
+                Ok(output) => println!("{}", output),
+                Err(e) => println!("Error: {}", e),
+            },
+            Err(e) => println!("Error: {}", e),
+        }
+    }
+
+    if let Some(history) = &history {
+        if let Err(e) = editor.save_history(history) {
+            warn!("Couldn't save the command history to {}: {}", history.display(), e);
+        }
+    }
+    Ok(())
+}
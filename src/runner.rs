@@ -0,0 +1,185 @@
+use std::any::Any;
+use std::fs::{read_dir, read_to_string};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use color_eyre::eyre::eyre;
+use color_eyre::Report;
+use tracing::debug;
+
+use crate::answer::Answer;
+use crate::memory::{measure, AllocStats};
+use crate::solver::Solver;
+
+/// The answer to one part of a day, and how long it took to compute
+#[derive(Debug)]
+pub struct PartRun {
+    pub part: u32,
+    pub answer: Result<Answer, Report>,
+    pub time: Duration,
+    /// Only measured when allocations are being counted
+    pub memory: Option<AllocStats>,
+}
+
+/// Everything we found out from running a single day against one input file
+#[derive(Debug)]
+pub struct DayRun {
+    pub day: u32,
+    pub title: &'static str,
+    pub input: PathBuf,
+    pub parse: Result<Duration, Report>,
+    pub parse_memory: Option<AllocStats>,
+    pub parts: Vec<PartRun>,
+}
+
+impl DayRun {
+    pub fn is_ok(&self) -> bool {
+        self.parse.is_ok() && self.parts.iter().all(|p| p.answer.is_ok())
+    }
+
+    pub fn total_time(&self) -> Duration {
+        self.parse.as_ref().copied().unwrap_or_default() + self.parts.iter().map(|p| p.time).sum::<Duration>()
+    }
+}
+
+/// Runs `f`, returning its result along with how long it took
+pub fn timed<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+/// Parses `input` with `solver` and solves both parts, timing each step
+pub fn run_day(solver: &dyn Solver, input: &Path) -> DayRun {
+    let mut run = DayRun {
+        day: solver.day(),
+        title: solver.title(),
+        input: input.to_owned(),
+        parse: Ok(Duration::default()),
+        parse_memory: None,
+        parts: vec![],
+    };
+
+    let text = match read_to_string(input) {
+        Ok(text) => text,
+        Err(e) => {
+            run.parse = Err(Report::new(e).wrap_err(format!("Couldn't read {}", input.display())));
+            return run
+        }
+    };
+
+    debug!("{file:?}: read {count} bytes", file=input, count=text.len());
+
+    let ((parsed, parse_time), parse_memory) = measure(|| timed(|| unpanicked(|| solver.parse(&text))));
+    run.parse_memory = parse_memory;
+    let parsed = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            run.parse = Err(e);
+            return run
+        }
+    };
+    run.parse = Ok(parse_time);
+
+    let ((answer, time), memory) = measure(|| timed(|| unpanicked(|| solver.part1(&parsed))));
+    run.parts.push(PartRun { part: 1, answer, time, memory });
+    let ((answer, time), memory) = measure(|| timed(|| unpanicked(|| solver.part2(&parsed))));
+    run.parts.push(PartRun { part: 2, answer, time, memory });
+
+    run
+}
+
+/// What a panic said, if it said anything
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match (payload.downcast_ref::<&str>(), payload.downcast_ref::<String>()) {
+        (Some(message), _) => message.to_string(),
+        (_, Some(message)) => message.clone(),
+        _ => "(no message)".to_owned(),
+    }
+}
+
+/// Runs part of a day, turning a panic into an error so that it's reported along with everything else
+fn unpanicked<T>(f: impl FnOnce() -> Result<T, Report>) -> Result<T, Report> {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| Err(eyre!("Panicked: {}", panic_message(payload.as_ref()))))
+}
+
+/// How long a batch of days took altogether
+#[derive(Debug, Copy, Clone)]
+pub struct Totals {
+    pub jobs: usize,
+    /// From starting the first day to finishing the last
+    pub wall: Duration,
+    /// Summed over every thread, or nothing where that can't be measured
+    pub cpu: Option<Duration>,
+}
+
+/// CPU time used by the whole process so far
+#[cfg(unix)]
+pub fn cpu_time() -> Option<Duration> {
+    let mut usage = std::mem::MaybeUninit::<libc::rusage>::zeroed();
+    // SAFETY: getrusage only writes to the struct it's given
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, usage.as_mut_ptr()) } != 0 {
+        return None
+    }
+    let usage = unsafe { usage.assume_init() };
+    let time = |t: libc::timeval| Duration::from_secs(t.tv_sec as u64) + Duration::from_micros(t.tv_usec as u64);
+    Some(time(usage.ru_utime) + time(usage.ru_stime))
+}
+
+#[cfg(not(unix))]
+pub fn cpu_time() -> Option<Duration> {
+    None
+}
+
+/// Runs each solver against its input on up to `jobs` threads at once, returning the runs in the same
+/// order as `days` however they finished
+pub fn run_days(days: &[(&dyn Solver, PathBuf)], jobs: usize) -> (Vec<DayRun>, Totals) {
+    let jobs = jobs.clamp(1, days.len().max(1));
+    let next = AtomicUsize::new(0);
+    let (cpu_before, start) = (cpu_time(), Instant::now());
+
+    let mut runs: Vec<(usize, DayRun)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs).map(|_| scope.spawn(|| {
+            let mut finished = vec![];
+            // Each worker takes the next day nobody has started yet, so slow days don't hold up the rest
+            loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some((solver, input)) = days.get(i) else { return finished };
+                finished.push((i, run_day(*solver, input)));
+            }
+        })).collect();
+        workers.into_iter().flat_map(|w| w.join().expect("a worker panicked outside of a day")).collect()
+    });
+    runs.sort_by_key(|(i, _)| *i);
+
+    let wall = start.elapsed();
+    let cpu = cpu_before.zip(cpu_time()).map(|(before, after)| after.saturating_sub(before));
+    (runs.into_iter().map(|(_, run)| run).collect(), Totals { jobs, wall, cpu })
+}
+
+/// The conventional location of a day's puzzle input
+pub fn default_input(data_dir: &Path, day: u32) -> PathBuf {
+    data_dir.join(format!("day{}.txt", day))
+}
+
+/// The other inputs kept for a day in `dir`, named `dayN-NAME.txt` like the examples, in name order; there
+/// are none if `dir` doesn't exist
+pub fn day_files(dir: &Path, day: u32) -> Result<Vec<PathBuf>, Report> {
+    if !dir.exists() {
+        return Ok(vec![])
+    }
+    let prefix = format!("day{}-", day);
+    let mut files = vec![];
+    for entry in read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        if name.starts_with(&prefix) && name.ends_with(".txt") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
@@ -0,0 +1,134 @@
+use std::any::Any;
+
+use color_eyre::eyre::eyre;
+use color_eyre::Report;
+use tracing::info_span;
+
+use crate::animation::Simulation;
+use crate::answer::Answer;
+use crate::picture::{ColorMap, Picture};
+use crate::random::Rng;
+use crate::{day1, day2, day3, day4, day5, day6, day7, day8, day9, day10, day11, day12, day13, day14, day15, day16, day17, day18, day19, day20};
+
+/// A day's parsed input, type-erased so that solvers for different days can share one interface
+pub type Parsed = Box<dyn Any>;
+
+/// What each day module implements: parse the input once, then solve both parts from the parsed model
+pub trait Puzzle {
+    type Input: 'static;
+
+    const DAY: u32;
+    const TITLE: &'static str;
+
+    fn parse(input: &str) -> Result<Self::Input, Report>;
+    fn part1(input: &Self::Input) -> Result<Answer, Report>;
+    fn part2(input: &Self::Input) -> Result<Answer, Report>;
+
+    /// A random input that parses, at roughly `size` (lines, grid width, or whatever the day has many of),
+    /// or at about the size of a real input if that's not given
+    fn generate(rng: &mut Rng, size: Option<usize>) -> String;
+
+    /// A picture of the puzzle's state, for days where there's something worth looking at, coloured with
+    /// `colors` or whatever suits the day best if that's not given
+    fn picture(_input: &Self::Input, _colors: Option<ColorMap>) -> Result<Option<Picture>, Report> {
+        Ok(None)
+    }
+
+    /// The puzzle's state as something that can be stepped through, for days that iterate one
+    fn simulation(_input: &Self::Input) -> Result<Option<Box<dyn Simulation>>, Report> {
+        Ok(None)
+    }
+
+    /// The answer to a part worked out the slow, obvious way, to check a shortcut against; only for days that
+    /// take one, and only for parts where the slow way finishes
+    fn reference(_input: &Self::Input, _part: u32) -> Result<Option<Answer>, Report> {
+        Ok(None)
+    }
+}
+
+/// Object-safe view of a `Puzzle`, so every day can be kept in the same registry
+pub trait Solver: Sync {
+    fn day(&self) -> u32;
+    fn title(&self) -> &'static str;
+    fn parse(&self, input: &str) -> Result<Parsed, Report>;
+    fn part1(&self, parsed: &Parsed) -> Result<Answer, Report>;
+    fn part2(&self, parsed: &Parsed) -> Result<Answer, Report>;
+    fn picture(&self, parsed: &Parsed, colors: Option<ColorMap>) -> Result<Option<Picture>, Report>;
+    fn simulation(&self, parsed: &Parsed) -> Result<Option<Box<dyn Simulation>>, Report>;
+    fn reference(&self, parsed: &Parsed, part: u32) -> Result<Option<Answer>, Report>;
+    fn generate(&self, rng: &mut Rng, size: Option<usize>) -> String;
+}
+
+fn downcast<P: Puzzle>(parsed: &Parsed) -> Result<&P::Input, Report> {
+    parsed.downcast_ref::<P::Input>()
+        .ok_or_else(|| eyre!("Parsed input was not produced by the day {} solver", P::DAY))
+}
+
+impl<P: Puzzle + Sync> Solver for P {
+    fn day(&self) -> u32 { P::DAY }
+    fn title(&self) -> &'static str { P::TITLE }
+
+    fn parse(&self, input: &str) -> Result<Parsed, Report> {
+        let _span = info_span!("parse", day = P::DAY).entered();
+        Ok(Box::new(P::parse(input)?))
+    }
+
+    fn part1(&self, parsed: &Parsed) -> Result<Answer, Report> {
+        let _span = info_span!("part1", day = P::DAY).entered();
+        P::part1(downcast::<P>(parsed)?)
+    }
+
+    fn part2(&self, parsed: &Parsed) -> Result<Answer, Report> {
+        let _span = info_span!("part2", day = P::DAY).entered();
+        P::part2(downcast::<P>(parsed)?)
+    }
+
+    fn picture(&self, parsed: &Parsed, colors: Option<ColorMap>) -> Result<Option<Picture>, Report> {
+        P::picture(downcast::<P>(parsed)?, colors)
+    }
+
+    fn simulation(&self, parsed: &Parsed) -> Result<Option<Box<dyn Simulation>>, Report> {
+        P::simulation(downcast::<P>(parsed)?)
+    }
+
+    fn reference(&self, parsed: &Parsed, part: u32) -> Result<Option<Answer>, Report> {
+        P::reference(downcast::<P>(parsed)?, part)
+    }
+
+    fn generate(&self, rng: &mut Rng, size: Option<usize>) -> String {
+        P::generate(rng, size)
+    }
+}
+
+/// Every registered day, in day order
+pub static SOLVERS: &[&dyn Solver] = &[
+    &day1::Day1,
+    &day2::Day2,
+    &day3::Day3,
+    &day4::Day4,
+    &day5::Day5,
+    &day6::Day6,
+    &day7::Day7,
+    &day8::Day8,
+    &day9::Day9,
+    &day10::Day10,
+    &day11::Day11,
+    &day12::Day12,
+    &day13::Day13,
+    &day14::Day14,
+    &day15::Day15,
+    &day16::Day16,
+    &day17::Day17,
+    &day18::Day18,
+    &day19::Day19,
+    &day20::Day20,
+];
+
+pub fn find(day: u32) -> Option<&'static dyn Solver> {
+    SOLVERS.iter().copied().find(|s| s.day() == day)
+}
+
+/// Like `find`, but an unknown day is an error
+pub fn get(day: u32) -> Result<&'static dyn Solver, Report> {
+    find(day).ok_or_else(|| eyre!("No such puzzle: {}", day))
+}
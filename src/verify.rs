@@ -0,0 +1,146 @@
+use std::fmt::{Display, Formatter};
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::eyre;
+use color_eyre::Report;
+use serde::Deserialize;
+
+use crate::answer::Answer;
+use crate::runner::{run_day, DayRun};
+use crate::solver;
+
+/// An expected answer as written in the answers file: either a number or some text
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Expected {
+    Integer(i64),
+    Text(String),
+}
+
+impl Expected {
+    pub fn matches(&self, answer: &Answer) -> bool {
+        match (self, answer) {
+            (Expected::Integer(n), Answer::Integer(a)) => n == a,
+            (Expected::Integer(_), _) => false,
+            (Expected::Text(s), Answer::Grid(rows)) => s.trim_end_matches('\n').split('\n').eq(rows.iter().map(|r| r.as_str())),
+            // Big numbers don't fit in a TOML integer, so they're written as strings
+            (Expected::Text(s), answer) => *s == answer.to_string(),
+        }
+    }
+}
+
+impl Display for Expected {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expected::Integer(n) => write!(f, "{}", n),
+            Expected::Text(s) => write!(f, "{}", s.trim_end_matches('\n')),
+        }
+    }
+}
+
+/// The expected answers for one day's input file
+#[derive(Debug, Deserialize)]
+pub struct ExpectedAnswers {
+    pub day: u32,
+    pub input: PathBuf,
+    pub part1: Option<Expected>,
+    pub part2: Option<Expected>,
+}
+
+impl ExpectedAnswers {
+    pub fn part(&self, part: u32) -> Option<&Expected> {
+        match part {
+            1 => self.part1.as_ref(),
+            2 => self.part2.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AnswersFile {
+    #[serde(rename = "answer", default)]
+    answers: Vec<ExpectedAnswers>,
+}
+
+/// Reads an answers file; input paths in it are relative to the file's own directory
+pub fn load_answers(path: &Path) -> Result<Vec<ExpectedAnswers>, Report> {
+    let text = read_to_string(path).map_err(|e| Report::new(e).wrap_err(format!("Couldn't read {}", path.display())))?;
+    let file: AnswersFile = toml::from_str(&text).map_err(|e| Report::new(e).wrap_err(format!("Couldn't parse {}", path.display())))?;
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+
+    Ok(file.answers.into_iter()
+        .map(|a| ExpectedAnswers { input: base.join(&a.input), ..a })
+        .collect())
+}
+
+/// A difference between what a solver produced and what the answers file says it should
+#[derive(Debug)]
+pub struct Mismatch {
+    pub day: u32,
+    pub part: Option<u32>,
+    pub input: PathBuf,
+    pub problem: String,
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.part {
+            Some(part) => write!(f, "day {} part {} ({}): {}", self.day, part, self.input.display(), self.problem),
+            None => write!(f, "day {} ({}): {}", self.day, self.input.display(), self.problem),
+        }
+    }
+}
+
+/// Compares a run against its expected answers, returning every way in which they disagree
+pub fn check(run: &DayRun, expected: &ExpectedAnswers) -> Vec<Mismatch> {
+    let mismatch = |part: Option<u32>, problem: String| Mismatch { day: run.day, part, input: run.input.clone(), problem };
+
+    if let Err(e) = &run.parse {
+        return vec![mismatch(None, format!("failed to parse: {:#}", e))]
+    }
+
+    let mut mismatches = vec![];
+    for part in &run.parts {
+        let expected = match expected.part(part.part) {
+            Some(expected) => expected,
+            None => continue,
+        };
+        match &part.answer {
+            Ok(answer) if expected.matches(answer) => {},
+            Ok(answer) => mismatches.push(mismatch(Some(part.part), format!("expected {}, got {}", expected, answer))),
+            Err(e) => mismatches.push(mismatch(Some(part.part), format!("expected {}, got error: {:#}", expected, e))),
+        }
+    }
+    mismatches
+}
+
+/// Runs every entry in the answers file (optionally only those for one day) and reports mismatches
+pub fn verify(answers_path: &Path, only_day: Option<u32>) -> Result<(), Report> {
+    let answers = load_answers(answers_path)?;
+    let mut checked = 0;
+    let mut mismatches = vec![];
+
+    for expected in answers.iter().filter(|a| only_day.is_none_or(|d| a.day == d)) {
+        let solver = solver::get(expected.day)?;
+        let run = run_day(solver, &expected.input);
+        let found = check(&run, expected);
+        if found.is_empty() {
+            println!("ok        day {:>2} ({})", expected.day, expected.input.display());
+        } else {
+            for m in &found {
+                println!("MISMATCH  {}", m);
+            }
+        }
+        checked += 1;
+        mismatches.extend(found);
+    }
+
+    if mismatches.is_empty() {
+        println!("{} inputs verified", checked);
+        Ok(())
+    } else {
+        Err(eyre!("{} mismatches in {} inputs", mismatches.len(), checked))
+    }
+}
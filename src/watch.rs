@@ -0,0 +1,137 @@
+//! Reruns a day whenever its input, or one of its examples, changes, and shows how the answers moved.
+
+use std::fmt::{Display, Formatter};
+use std::fs::metadata;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::{Duration, SystemTime};
+
+use color_eyre::Report;
+use tracing::info;
+
+use crate::runner::{day_files, run_day, DayRun};
+use crate::solver::Solver;
+
+/// What each part came out as for each input file, in the order they were run: the answer, or the error
+/// if there wasn't one
+pub type Outcomes = Vec<((PathBuf, u32), String)>;
+
+/// How one part's outcome differs from the previous run
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Change {
+    Added(String),
+    Changed { before: String, after: String },
+    Unchanged(String),
+    Removed(String),
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // Multi-line answers start on their own line so the rows stay lined up
+        let show = |s: &str| if s.contains('\n') { format!("\n{}", s) } else { format!(" {}", s) };
+        match self {
+            Change::Added(now) => write!(f, "new:{}", show(now)),
+            Change::Changed { before, after } => {
+                let separator = if before.contains('\n') { "\n" } else { ", " };
+                write!(f, "changed: was{}{}now{}", show(before), separator, show(after))
+            }
+            Change::Unchanged(now) => write!(f, "unchanged:{}", show(now)),
+            Change::Removed(before) => write!(f, "gone: was{}", show(before)),
+        }
+    }
+}
+
+pub fn outcomes(runs: &[DayRun]) -> Outcomes {
+    let mut outcomes = Outcomes::new();
+    for run in runs {
+        match &run.parse {
+            Err(e) => {
+                for part in 1..=2 {
+                    outcomes.push(((run.input.clone(), part), format!("error: {}", e)));
+                }
+            }
+            Ok(_) => {
+                for part in &run.parts {
+                    let outcome = match &part.answer {
+                        Ok(answer) => answer.to_string(),
+                        Err(e) => format!("error: {}", e),
+                    };
+                    outcomes.push(((run.input.clone(), part.part), outcome));
+                }
+            }
+        }
+    }
+    outcomes
+}
+
+fn find<'a>(outcomes: &'a Outcomes, key: &(PathBuf, u32)) -> Option<&'a String> {
+    outcomes.iter().find(|(k, _)| k == key).map(|(_, outcome)| outcome)
+}
+
+/// Compares every part in the current run with the previous one, followed by any parts that have gone
+pub fn diff(previous: &Outcomes, current: &Outcomes) -> Vec<((PathBuf, u32), Change)> {
+    let mut changes: Vec<_> = current.iter().map(|(key, now)| {
+        let change = match find(previous, key) {
+            None => Change::Added(now.clone()),
+            Some(before) if before == now => Change::Unchanged(now.clone()),
+            Some(before) => Change::Changed { before: before.clone(), after: now.clone() },
+        };
+        (key.clone(), change)
+    }).collect();
+
+    changes.extend(previous.iter()
+        .filter(|(key, _)| find(current, key).is_none())
+        .map(|(key, before)| (key.clone(), Change::Removed(before.clone()))));
+    changes
+}
+
+/// The input, followed by the day's examples (`dayN-NAME.txt`) in `examples` if there is one
+fn watched_files(day: u32, input: &Path, examples: Option<&Path>) -> Result<Vec<PathBuf>, Report> {
+    let mut files = match examples {
+        Some(dir) => day_files(dir, day)?,
+        None => vec![],
+    };
+    files.retain(|path| path != input);
+    files.insert(0, input.to_owned());
+    Ok(files)
+}
+
+/// When each file was last modified and how big it is, or nothing if it can't be read
+fn fingerprints(files: &[PathBuf]) -> Vec<Option<(SystemTime, u64)>> {
+    files.iter()
+        .map(|file| metadata(file).ok().and_then(|m| Some((m.modified().ok()?, m.len()))))
+        .collect()
+}
+
+/// Polls the watched files every `interval`, rerunning `solver` on all of them whenever any has changed and
+/// writing how the answers differ from the previous run to `out`. Only returns if something goes wrong.
+pub fn watch(solver: &dyn Solver, input: &Path, examples: Option<&Path>, interval: Duration, out: &mut impl Write) -> Result<(), Report> {
+    let mut previous = Outcomes::new();
+    let mut last_seen = None;
+    let mut run = 0;
+
+    loop {
+        // The examples are listed again each time so that new ones get picked up
+        let files = watched_files(solver.day(), input, examples)?;
+        let seen = (files.clone(), fingerprints(&files));
+        if last_seen.as_ref() != Some(&seen) {
+            if last_seen.is_some() {
+                info!("change detected, running day {} again", solver.day());
+            }
+            run += 1;
+
+            let runs: Vec<_> = files.iter().map(|file| run_day(solver, file)).collect();
+            let current = outcomes(&runs);
+            writeln!(out, "--- run {} ---", run)?;
+            for ((file, part), change) in diff(&previous, &current) {
+                writeln!(out, "{} part {}: {}", file.display(), part, change)?;
+            }
+            out.flush()?;
+
+            previous = current;
+            last_seen = Some(seen);
+        }
+        sleep(interval);
+    }
+}
@@ -0,0 +1,401 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use advent21::animation::{bar_chart, half_blocks};
+use advent21::answer::Answer;
+use advent21::automaton::{Automaton, Boundary, Neighborhood};
+use advent21::bench::{percent_change, Stats};
+use advent21::crosscheck::{self, crosscheck};
+use advent21::day10::Day10;
+use advent21::day13::Day13;
+use advent21::day15::{Cave, Day15};
+use advent21::day16::{Day16, PacketType};
+use advent21::day19::{align, survey, Day19};
+use advent21::day5::Day5;
+use advent21::day6::Day6;
+use advent21::day7::Day7;
+use advent21::day9::Day9;
+use advent21::fuzz::replay;
+use advent21::geometry::{Point2, Point3, Rotation, Segment};
+use advent21::grid::Grid;
+use advent21::parse_error::ParseError;
+use advent21::pathfinding::{bfs, dijkstra};
+use advent21::picture::{Color, ColorMap, ImageFormat, Picture};
+use advent21::random::Rng;
+use advent21::repl::{complete, Command, Session};
+use advent21::runner::run_days;
+use advent21::solver::{self, Puzzle};
+use advent21::watch::{diff, Change};
+use color_eyre::Report;
+use tracing::span::{Attributes, Id};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::prelude::*;
+
+#[test]
+fn bits_decoder_is_usable_directly() {
+    let packet = Day16::parse("D2FE28").unwrap();
+    assert_eq!(packet.version, 6);
+    assert!(matches!(packet.content, PacketType::LiteralValue(ref v) if v.value == 2021));
+}
+
+#[test]
+fn bits_values_too_big_for_a_u64_are_errors() {
+    // A sum of u64::MAX and 5
+    let packet = Day16::parse("0001844FFFFFFFFFFFFFFFFFFEF10A").unwrap();
+    let report = Day16::part2(&packet).unwrap_err();
+    assert!(report.to_string().contains("doesn't fit in 64 bits"), "{}", report);
+}
+
+#[test]
+fn completion_scores_too_big_for_a_u64_are_errors() {
+    let lines = Day10::parse(&"(".repeat(30)).unwrap();
+    assert_eq!(Day10::part1(&lines).unwrap(), 0.into());
+    assert!(Day10::part2(&lines).is_err());
+}
+
+#[test]
+fn scanner_alignment_is_usable_directly() {
+    let input = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/data/day19-example.txt")).unwrap();
+    let scanners = Day19::parse(&input).unwrap();
+    let aligned = align(&scanners).unwrap();
+    let scanner1 = aligned.iter().find(|(_, s)| s.number == 1).unwrap();
+    assert_eq!(scanner1.0, Point3::new(68, -1246, -43));
+}
+
+#[test]
+fn parse_errors_point_at_the_problem() {
+    let report = Day5::parse("0,9 -> 5,9\n8,0 -> 0;8\n").unwrap_err();
+    let error = report.downcast_ref::<ParseError>().unwrap();
+    assert_eq!((error.line, error.column), (2, 9));
+    assert_eq!(error.snippet, "  |\n2 | 8,0 -> 0;8\n  |         ^");
+}
+
+#[test]
+fn vent_lines_must_run_along_the_grid_or_at_45_degrees() {
+    let report = Day5::parse("0,9 -> 5,9\n0,0 -> 3,1\n").unwrap_err();
+    assert!(report.to_string().starts_with("Line 2 isn't horizontal"), "{}", report);
+    assert!(Day5::parse("0,9 -> 5,9\n0,0 -> 3,3\n3,3 -> 3,0\n").is_ok());
+}
+
+#[test]
+fn number_lists_allow_a_trailing_newline() {
+    assert_eq!(Day7::parse("16,1,2\n").unwrap(), vec![16, 1, 2]);
+}
+
+#[test]
+fn lanternfish_timers_only_go_up_to_8() {
+    assert!(Day6::parse("3,9").is_err());
+    let counts = Day6::parse("8").unwrap();
+    assert_eq!(Day6::part1(&counts).unwrap(), Day6::reference(&counts, 1).unwrap().unwrap());
+}
+
+#[test]
+fn digit_grids_must_be_rectangular() {
+    let report = Day9::parse("2199\n398\n").unwrap_err();
+    let error = report.downcast_ref::<ParseError>().unwrap();
+    assert_eq!((error.line, error.column), (2, 4));
+    assert_eq!(error.expected, "a row as wide as the first");
+}
+
+#[test]
+fn bits_decoder_rejects_non_hex_digits() {
+    let report = Day16::parse("D2FG28").unwrap_err();
+    let error = report.downcast_ref::<ParseError>().unwrap();
+    assert_eq!((error.line, error.column), (1, 4));
+}
+
+#[test]
+fn grid_neighbors_stay_on_the_grid() {
+    let grid = Grid::from_rows(vec![vec![1, 2, 3], vec![4, 5, 6]]).unwrap();
+    assert_eq!(grid.neighbors4((0, 0)).collect::<Vec<_>>(), vec![(0, 1), (1, 0)]);
+    assert_eq!(grid.neighbors8((1, 2)).count(), 3);
+    assert_eq!(grid.get_signed(-1, 0), None);
+
+    let tiled = grid.tile(2, 2, |&n, (down, across)| n + 10 * (down + across));
+    assert_eq!((tiled.width(), tiled.height()), (6, 4));
+    assert_eq!(tiled[(2, 4)], 22);
+    assert_eq!(grid.to_string(), "123\n456\n");
+}
+
+#[test]
+fn there_are_24_rotations() {
+    let rotations = Rotation::all();
+    let p = Point3::new(1, 2, 3);
+    let images: std::collections::HashSet<_> = rotations.iter().map(|r| r.apply(p)).collect();
+    assert_eq!(images.len(), 24);
+    for r in &rotations {
+        assert_eq!(r.compose(&r.inverse()), Rotation::IDENTITY);
+        assert_eq!(r.apply(p).manhattan_distance(Point3::ORIGIN), 6);
+    }
+}
+
+#[test]
+fn diagonal_segments_cover_every_point() {
+    let segment = Segment::new(Point2::new(9, 7), Point2::new(7, 9));
+    assert!(segment.is_diagonal());
+    assert_eq!(segment.points().collect::<Vec<_>>(), vec![Point2::new(9, 7), Point2::new(8, 8), Point2::new(7, 9)]);
+}
+
+#[test]
+fn searches_find_the_same_cheapest_route() {
+    let input = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/data/day15-example.txt")).unwrap();
+    let cave = Cave::new(Day15::parse(&input).unwrap());
+
+    let route = cave.safest_route().unwrap();
+    assert_eq!(route.cost, 40);
+    assert_eq!(route.nodes.first(), Some(&(0, 0)));
+    assert_eq!(route.nodes.iter().skip(1).map(|&p| cave.risks[p] as u64).sum::<u64>(), 40);
+    assert_eq!(dijkstra(&cave, (0, 0), |p| p == (9, 9)).unwrap().cost, 40);
+
+    // The fewest steps is straight across and down, whatever the risk
+    assert_eq!(bfs(&cave, (0, 0), |p| p == (9, 9)).unwrap().len(), 19);
+}
+
+#[test]
+fn automata_step_every_cell_at_once() {
+    // A herd of east-facing sea cucumbers: each moves right if the space in front was empty
+    let herd = |row: &str| Grid::from_rows(vec![row.chars().collect()]).unwrap();
+    let east = |&c: &char, sides: &[char]| match (c, sides[0], sides[1]) {
+        ('.', '>', _) => '>',
+        ('>', _, '.') => '.',
+        _ => c,
+    };
+
+    let mut wrapping = Automaton::new(herd("...>>>>>.>"), Neighborhood::Custom(vec![(0, -1), (0, 1)]), Boundary::Wrapping);
+    assert_eq!(wrapping.step(east), 4);
+    assert_eq!(wrapping.cells(), &herd(">..>>>>.>."));
+
+    // Against a wall they pile up and stop
+    let mut walled = Automaton::new(herd(">.>..>.."), Neighborhood::Custom(vec![(0, -1), (0, 1)]), Boundary::Fixed('#'));
+    assert_eq!(walled.run_until_stable(east), 6);
+    assert_eq!(walled.cells(), &herd(".....>>>"));
+}
+
+#[test]
+fn pictures_scale_up_each_cell() {
+    let grid = Grid::from_rows(vec![vec![true, false], vec![false, true]]).unwrap();
+    let picture = Picture::new(&grid, |_, &on| if on { Color::BLACK } else { Color::WHITE }).with_scale(3);
+    assert_eq!((picture.width(), picture.height()), (6, 6));
+
+    let mut pbm = vec![];
+    picture.write(&mut pbm, ImageFormat::Pbm).unwrap();
+    assert_eq!(pbm, b"P4\n6 6\n\xe0\xe0\xe0\x1c\x1c\x1c");
+
+    let mut png = vec![];
+    picture.write(&mut png, ImageFormat::Png).unwrap();
+    assert!(png.starts_with(b"\x89PNG"));
+}
+
+#[test]
+fn pictures_use_the_colour_map_they_are_given() {
+    let day11 = solver::find(11).unwrap();
+    let parsed = day11.parse(include_str!("../data/day11-example.txt")).unwrap();
+    let grey = day11.picture(&parsed, Some("mono".parse().unwrap())).unwrap().unwrap();
+    assert!(grey.cells().iter().all(|c| c.r == c.g && c.g == c.b));
+    let heat = day11.picture(&parsed, None).unwrap().unwrap();
+    assert_eq!(heat.cells()[(0, 0)], Color::heat(5, 9));
+
+    assert!("rainbow".parse::<ColorMap>().is_err());
+}
+
+#[test]
+fn animation_frames_fit_the_state() {
+    let chart = bar_chart(&[("a".to_string(), 4), ("bc".to_string(), 2)], 8);
+    assert_eq!(chart, " a ████████ 4\nbc ████ 2\n");
+
+    // Three rows of cells take two lines, the last with nothing below it
+    let grid = Grid::new(2, 3, true);
+    let frame = half_blocks(&grid, |_| Color::WHITE);
+    assert_eq!(frame.lines().count(), 2);
+    assert_eq!(frame.matches('▀').count(), 4);
+}
+
+#[test]
+fn watching_shows_how_answers_changed() {
+    let outcome = |file: &str, part, answer: &str| ((PathBuf::from(file), part), answer.to_string());
+    let previous = vec![outcome("day1.txt", 1, "7"), outcome("day1.txt", 2, "5"), outcome("day1-old.txt", 1, "3")];
+    let current = vec![outcome("day1.txt", 1, "7"), outcome("day1.txt", 2, "6"), outcome("day1-new.txt", 1, "2")];
+
+    let changes: Vec<_> = diff(&previous, &current).into_iter().map(|(_, change)| change).collect();
+    assert_eq!(changes, vec![
+        Change::Unchanged("7".to_string()),
+        Change::Changed { before: "5".to_string(), after: "6".to_string() },
+        Change::Added("2".to_string()),
+        Change::Removed("3".to_string()),
+    ]);
+    assert_eq!(changes[1].to_string(), "changed: was 5, now 6");
+}
+
+#[test]
+fn parallel_runs_come_back_in_day_order() {
+    let data = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/data"));
+    let days: Vec<_> = solver::SOLVERS.iter().map(|s| (*s, data.join(format!("day{}-example.txt", s.day())))).collect();
+
+    let (runs, totals) = run_days(&days, 4);
+    assert_eq!(totals.jobs, 4);
+    assert_eq!(runs.iter().map(|r| r.day).collect::<Vec<_>>(), (1..=20).collect::<Vec<_>>());
+}
+
+/// A day that can't cope with its second part
+struct Panicky;
+
+impl Puzzle for Panicky {
+    type Input = ();
+    const DAY: u32 = 99;
+    const TITLE: &'static str = "Panicky";
+
+    fn parse(_input: &str) -> Result<(), Report> { Ok(()) }
+    fn part1(_input: &()) -> Result<Answer, Report> { Ok(1.into()) }
+    fn part2(_input: &()) -> Result<Answer, Report> { panic!("out of ideas") }
+    fn generate(_rng: &mut Rng, _size: Option<usize>) -> String { String::new() }
+}
+
+#[test]
+fn a_day_that_panics_doesnt_stop_the_others() {
+    let data = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/data"));
+    let days = [(&Panicky as &dyn solver::Solver, data.join("day1-example.txt")), (solver::find(1).unwrap(), data.join("day1-example.txt"))];
+
+    let (runs, _) = run_days(&days, 2);
+    assert!(runs[0].parts[0].answer.is_ok());
+    let report = runs[0].parts[1].answer.as_ref().unwrap_err();
+    assert!(report.to_string().contains("out of ideas"), "{}", report);
+    assert!(runs[1].is_ok());
+}
+
+#[test]
+fn benchmark_stats_summarise_the_samples() {
+    let ms = Duration::from_millis;
+    let stats = Stats::from_samples(vec![ms(4), ms(1), ms(3), ms(2)]);
+    assert_eq!((stats.min, stats.median, stats.mean), (ms(1), Duration::from_micros(2500), Duration::from_micros(2500)));
+    assert!((stats.stddev.as_secs_f64() * 1000.0 - 1.291).abs() < 0.001);
+    assert!((percent_change(ms(200), ms(150)) + 25.0).abs() < 1e-9);
+}
+
+#[test]
+fn repl_sessions_keep_the_parsed_input() {
+    let mut session = Session::new(solver::find(14).unwrap(), include_str!("../data/day14-example.txt")).unwrap();
+    let mut run = |line: &str| session.run(&line.parse::<Command>().unwrap());
+
+    assert!(run("step 10").unwrap().starts_with("10 steps taken"));
+    assert!(run("show").unwrap().starts_with("Step 10: length 3073, most minus least common 1588"));
+    assert!(run("set template=NN").is_ok());
+    assert!(run("set steps=3").is_err());
+    assert!(run("show").unwrap().starts_with("Step 0: length 2"));
+    // Solving still uses the input as it was parsed
+    assert!(run("part1").unwrap().starts_with("1588 ("));
+
+    assert!("step lots".parse::<Command>().is_err());
+    assert_eq!("set path = 0.1".parse::<Command>().unwrap(), Command::Set { param: "path".into(), value: "0.1".into() });
+    assert_eq!(complete("s", 1, &["path"]), (0, vec!["step".to_string(), "show".to_string(), "set".to_string()]));
+    assert_eq!(complete("set pa", 6, &["path"]), (4, vec!["path=".to_string()]));
+}
+
+/// Remembers the name of every span that gets opened
+struct SpanNames(Arc<Mutex<Vec<&'static str>>>);
+
+impl<S: Subscriber> Layer<S> for SpanNames {
+    fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+        self.0.lock().unwrap().push(attrs.metadata().name());
+    }
+}
+
+#[test]
+fn solving_opens_a_span_for_each_phase() {
+    let names = Arc::new(Mutex::new(vec![]));
+    let subscriber = tracing_subscriber::registry().with(SpanNames(names.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        let solver = solver::find(20).unwrap();
+        let parsed = solver.parse(include_str!("../data/day20-example.txt")).unwrap();
+        solver.part1(&parsed).unwrap();
+    });
+
+    let names = names.lock().unwrap();
+    assert_eq!(names[..3], ["parse", "part1", "enhance"]);
+    assert_eq!(names.iter().filter(|&&n| n == "enhance").count(), 2);
+}
+
+#[test]
+fn inputs_that_used_to_crash_are_handled() {
+    let corpus = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/fuzz/corpus"));
+    let failures = replay(&corpus, Duration::from_secs(30)).unwrap();
+    let failures: Vec<_> = failures.iter().map(|f| format!("{}: {}", f.saved.display(), f.outcome)).collect();
+    assert!(failures.is_empty(), "{:#?}", failures);
+}
+
+#[test]
+fn generated_inputs_parse_and_repeat_for_the_same_seed() {
+    for solver in solver::SOLVERS {
+        let input = solver.generate(&mut Rng::new(1), Some(5));
+        assert_eq!(solver.generate(&mut Rng::new(1), Some(5)), input, "day {}", solver.day());
+        assert_ne!(solver.generate(&mut Rng::new(2), Some(5)), input, "day {}", solver.day());
+
+        let parsed = solver.parse(&input).unwrap_or_else(|e| panic!("day {}: {}\n{}", solver.day(), e, input));
+        solver.part1(&parsed).unwrap_or_else(|e| panic!("day {}: {}\n{}", solver.day(), e, input));
+    }
+}
+
+#[test]
+fn generated_packets_always_have_a_value() {
+    for seed in 0..30 {
+        let input = Day16::generate(&mut Rng::new(seed), None);
+        let packet = Day16::parse(&input).unwrap();
+        Day16::part2(&packet).unwrap_or_else(|e| panic!("seed {}: {}", seed, e));
+    }
+}
+
+#[test]
+fn generated_scanners_are_found_where_they_really_are() {
+    let survey = survey(&mut Rng::new(3), 6);
+    let aligned = align(&survey.scanners).unwrap();
+    for (position, scanner) in &aligned {
+        assert_eq!(*position, survey.positions[scanner.number as usize]);
+    }
+    let beacons: HashSet<Point3> = aligned.iter().flat_map(|(position, scanner)| scanner.beacons.iter().map(move |&b| b + *position)).collect();
+    assert_eq!(beacons.len(), survey.beacons.len());
+}
+
+#[test]
+fn shortcuts_agree_with_the_reference_answers() {
+    let options = crosscheck::Options {
+        runs: 5,
+        seed: 0,
+        size: Some(20),
+        examples: PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/data")),
+    };
+    for day in [6, 7, 14, 17] {
+        let summary = crosscheck(solver::find(day).unwrap(), &options).unwrap();
+        assert!(summary.compared > 0, "day {} has nothing to compare", day);
+        assert!(summary.disagreements.is_empty(), "{:#?}", summary.disagreements);
+    }
+}
+
+#[test]
+fn crosschecks_dont_need_examples() {
+    let options = crosscheck::Options { runs: 3, seed: 0, size: Some(10), examples: PathBuf::from("/nonexistent") };
+    let summary = crosscheck(solver::find(6).unwrap(), &options).unwrap();
+    assert_eq!(summary.compared, options.runs, "{:?}", summary.disagreements);
+    assert_eq!(crosscheck(solver::find(1).unwrap(), &options).unwrap().compared, 0);
+}
+
+#[test]
+fn paper_too_big_to_hold_is_an_error() {
+    assert!(Day13::parse("100000,100000\n\nfold along x=200000\n").is_err());
+    assert!(Day13::parse("1,1\n\nfold along y=2147483647\n").is_err());
+    assert!(Day13::parse("1,1\n\nfold along y=4095\n").is_ok());
+}
+
+#[test]
+fn crabs_line_up_on_the_median_despite_stragglers() {
+    // The average is far enough off that the cheapest position isn't anywhere near it
+    let positions = Day7::parse("0,0,0,0,0,0,1900,1900,1900").unwrap();
+    assert_eq!(Day7::part1(&positions).unwrap(), 5700.into());
+}
+
+#[test]
+fn every_day_is_registered() {
+    let days: Vec<_> = solver::SOLVERS.iter().map(|s| s.day()).collect();
+    assert_eq!(days, (1..=20).collect::<Vec<_>>());
+}
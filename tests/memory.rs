@@ -0,0 +1,24 @@
+//! Allocation counting needs its own test binary, since it has to be the global allocator.
+
+use advent21::memory::{measure, CountingAllocator};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[test]
+fn allocations_are_counted_per_measurement() {
+    let (_, outer) = measure(|| {
+        let small = vec![0u8; 1000];
+        drop(small);
+        let (kept, inner) = measure(|| vec![0u64; 500]);
+        let inner = inner.unwrap();
+        assert_eq!((inner.allocations, inner.bytes, inner.peak_bytes), (1, 4000, 4000));
+        kept
+    });
+
+    let outer = outer.unwrap();
+    assert_eq!(outer.allocations, 2);
+    assert_eq!(outer.bytes, 5000);
+    // The first vector was freed before the second was made
+    assert_eq!(outer.peak_bytes, 4000);
+}
@@ -0,0 +1,130 @@
+use std::collections::HashSet;
+use std::ops::Range;
+
+use proptest::prelude::*;
+
+use advent21::day11::Day11;
+use advent21::day13::{fold, Instruction};
+use advent21::day16::{Day16, LiteralValuePacket, OperatorPacket, Packet, PacketType};
+use advent21::day18::{format_node, is_reduced, magnitude, reduce, Day18, NodeBehavior};
+use advent21::day19::{find_transform, Scanner};
+use advent21::geometry::{Point2, Point3, Rotation};
+use advent21::random::Rng;
+use advent21::solver::Puzzle;
+
+/// A reduced snailfish number whose outermost pair is at `depth`
+fn snailfish(depth: u32) -> BoxedStrategy<String> {
+    let number = (0..10u8).prop_map(|n| n.to_string());
+    // Pairs nested four deep would explode
+    let element = if depth == 3 { number.boxed() } else { prop_oneof![number, snailfish(depth + 1)].boxed() };
+    (element.clone(), element).prop_map(|(a, b)| format!("[{},{}]", a, b)).boxed()
+}
+
+fn snailfish_sum() -> impl Strategy<Value = (String, String)> {
+    (snailfish(0), snailfish(0))
+}
+
+proptest! {
+    #[test]
+    fn reducing_a_snailfish_sum_twice_changes_nothing((a, b) in snailfish_sum()) {
+        let numbers = Day18::parse(&format!("{}\n{}\n", a, b)).unwrap();
+        let sum = numbers[0].deep_copy().add(numbers[1].deep_copy());
+        reduce(&sum);
+        let reduced = format_node(&sum);
+        prop_assert!(is_reduced(&sum));
+
+        reduce(&sum);
+        prop_assert_eq!(format_node(&sum), reduced);
+    }
+
+    #[test]
+    fn snailfish_magnitude_only_depends_on_the_number((a, b) in snailfish_sum()) {
+        let numbers = Day18::parse(&format!("{}\n{}\n", a, b)).unwrap();
+        // Adding reduces the numbers in place, which mustn't touch the parsed input
+        prop_assert_eq!(Day18::part1(&numbers).unwrap(), Day18::part1(&numbers).unwrap());
+        prop_assert_eq!(Day18::part2(&numbers).unwrap(), Day18::part2(&numbers).unwrap());
+
+        // Writing the sum out and reading it back in doesn't change it either
+        let sum = numbers[0].deep_copy().add(numbers[1].deep_copy());
+        reduce(&sum);
+        let reparsed = Day18::parse(&format_node(&sum)).unwrap();
+        prop_assert_eq!(magnitude(&reparsed[0]), magnitude(&sum));
+    }
+}
+
+fn instruction() -> impl Strategy<Value = Instruction> {
+    prop_oneof![(1..40).prop_map(Instruction::FoldAlongX), (1..40).prop_map(Instruction::FoldAlongY)]
+}
+
+fn as_set(dots: &[Point2]) -> HashSet<Point2> {
+    dots.iter().copied().collect()
+}
+
+proptest! {
+    #[test]
+    fn folding_along_the_same_line_again_does_nothing(
+        dots in prop::collection::vec((0..80, 0..80).prop_map(|(x, y)| Point2::new(x, y)), 0..50),
+        instruction in instruction(),
+    ) {
+        let once = fold(dots, &instruction);
+        let twice = fold(once.clone(), &instruction);
+        prop_assert_eq!(as_set(&twice), as_set(&once));
+        prop_assert_eq!(twice.len(), once.len());
+    }
+}
+
+fn point(range: i32) -> impl Strategy<Value = Point3> {
+    (-range..=range, -range..=range, -range..=range).prop_map(|(x, y, z)| Point3::new(x, y, z))
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn found_transforms_map_shared_beacons_exactly(
+        shared in prop::collection::hash_set(point(400), 12..16),
+        rotation in prop::sample::select(Rotation::all()),
+        position in point(400),
+    ) {
+        // The second scanner sits at `position`, turned by `rotation`, and sees the same beacons
+        let shared: Vec<Point3> = shared.into_iter().collect();
+        let undo = rotation.inverse();
+        let base = Scanner { number: 0, beacons: shared.clone() };
+        let target = Scanner { number: 1, beacons: shared.iter().map(|&b| undo.apply(b - position)).collect() };
+
+        let (offset, found) = find_transform(&base, &target).expect("the scanners share every beacon");
+        for (&seen, &actual) in target.beacons.iter().zip(&shared) {
+            prop_assert_eq!(found.apply(seen) + offset, actual);
+        }
+    }
+}
+
+fn operator(subpacket: BoxedStrategy<Packet>, type_ids: Range<u8>, count: Range<usize>) -> impl Strategy<Value = Packet> {
+    (type_ids, 0..8u8, prop::collection::vec(subpacket, count))
+        .prop_map(|(type_id, version, subpackets)| Packet { version, content: PacketType::Operator(OperatorPacket { type_id, subpackets }) })
+}
+
+fn packet() -> impl Strategy<Value = Packet> {
+    let literal = (0..8u8, any::<u64>())
+        .prop_map(|(version, value)| Packet { version, content: PacketType::LiteralValue(LiteralValuePacket { value }) });
+    literal.prop_recursive(4, 64, 5, |subpacket| prop_oneof![
+        // Comparisons have exactly two subpackets, and everything else at least one
+        operator(subpacket.clone(), 0..4, 1..5),
+        operator(subpacket, 5..8, 2..3),
+    ])
+}
+
+proptest! {
+    #[test]
+    fn packets_decode_to_what_was_encoded(packet in packet()) {
+        prop_assert_eq!(Day16::parse(&packet.encode()).unwrap(), packet);
+    }
+}
+
+proptest! {
+    #[test]
+    fn generated_octopuses_all_flash_at_once(seed in any::<u64>(), side in 1..40usize) {
+        let grid = Day11::parse(&Day11::generate(&mut Rng::new(seed), Some(side))).unwrap();
+        prop_assert!(Day11::part2(&grid).is_ok());
+    }
+}